@@ -1,8 +1,10 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, contracterror, symbol_short,
-    Address, Env, IntoVal, Symbol, Vec, log,
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractimpl, contracttype, contracterror, log, symbol_short,
+    token, Address, BytesN, Env, IntoVal, Map, String, Symbol, Val, Vec,
 };
 
 // ============================================================================
@@ -31,6 +33,113 @@ pub struct Loan {
     pub last_interest_update: u64,
     pub due_date: u64,
     pub status: LoanStatus,
+    /// First time a keeper call saw the loan liquidatable (0 = not yet)
+    pub liquidatable_since: u64,
+    pub metadata_uri: Option<String>,
+    /// Off-chain facility reference (all zeros = none)
+    pub external_ref: BytesN<32>,
+    /// LTV is above `warning_threshold` and `ltv_warn` has been emitted
+    pub warned: bool,
+    /// Until this time partial repayments only cover interest (0 = no interest-only period)
+    pub interest_only_until: u64,
+    /// When `collateral_value` was last priced (origination or `refresh_collateral`)
+    pub collateral_refreshed_at: u64,
+    /// Fixed amount owed at maturity for a zero-coupon loan (0 = rate-based)
+    pub fixed_repayment: i128,
+    /// Config terms fixed at origination; `set_config` doesn't reach existing loans
+    pub terms: LoanTerms,
+}
+
+/// The part of `BorrowConfig` a loan agreement fixes: when and how the loan
+/// can be liquidated and what it accrues after maturity.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoanTerms {
+    /// Blended collateral factor of the basket; caps tranche draws
+    pub max_ltv: i128,
+    pub liquidation_threshold: i128,
+    pub liquidation_penalty: i128,
+    pub liquidation_escalation: i128,
+    pub max_liquidation_escalation: i128,
+    pub accrual_after_due: AccrualAfterDue,
+    pub late_penalty_rate: i128,
+    pub late_fee_bps: i128,
+    pub prepayment_fee_bps: i128,
+    pub prepayment_free_window_secs: u64,
+}
+
+impl LoanTerms {
+    fn from_config(config: &BorrowConfig) -> Self {
+        LoanTerms {
+            max_ltv: config.max_ltv,
+            liquidation_threshold: config.liquidation_threshold,
+            liquidation_penalty: config.liquidation_penalty,
+            liquidation_escalation: config.liquidation_escalation,
+            max_liquidation_escalation: config.max_liquidation_escalation,
+            accrual_after_due: config.accrual_after_due.clone(),
+            late_penalty_rate: config.late_penalty_rate,
+            late_fee_bps: config.late_fee_bps,
+            prepayment_fee_bps: config.prepayment_fee_bps,
+            prepayment_free_window_secs: config.prepayment_free_window_secs,
+        }
+    }
+}
+
+/// Loan layout stored under `DataKey::Loan` before `DataKey::LoanV2`.
+/// Read-only; a legacy loan is rewritten as V2 the next time it is saved,
+/// taking the config in force at upgrade as its terms.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct LegacyLoan {
+    pub id: u64,
+    pub borrower: Address,
+    pub receivable_ids: Vec<u64>,
+    pub collateral_value: i128,
+    pub principal: i128,
+    pub interest_rate: i128,
+    pub accrued_interest: i128,
+    pub borrowed_at: u64,
+    pub last_interest_update: u64,
+    pub due_date: u64,
+    pub status: LoanStatus,
+}
+
+impl LegacyLoan {
+    fn upgrade(self, env: &Env) -> Loan {
+        Loan {
+            id: self.id,
+            borrower: self.borrower,
+            receivable_ids: self.receivable_ids,
+            collateral_value: self.collateral_value,
+            principal: self.principal,
+            interest_rate: self.interest_rate,
+            accrued_interest: self.accrued_interest,
+            borrowed_at: self.borrowed_at,
+            last_interest_update: self.last_interest_update,
+            due_date: self.due_date,
+            status: self.status,
+            liquidatable_since: 0,
+            metadata_uri: None,
+            external_ref: BytesN::from_array(env, &[0; 32]),
+            warned: false,
+            interest_only_until: 0,
+            collateral_refreshed_at: self.borrowed_at,
+            fixed_repayment: 0,
+            terms: LoanTerms::from_config(&env.storage().instance().get(&DataKey::Config).unwrap()),
+        }
+    }
+}
+
+/// How interest behaves once a loan is past its `due_date`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccrualAfterDue {
+    /// Contractual rate keeps running
+    Continue,
+    /// Contractual rate stops; `late_penalty_rate` applies instead
+    PenaltyOnly,
+    /// Nothing accrues after maturity
+    Stop,
 }
 
 #[contracttype]
@@ -41,7 +150,125 @@ pub struct BorrowConfig {
     pub liquidation_penalty: i128,
     pub base_interest_rate: i128,
     pub max_loan_duration: u64,
+    /// Shortest loan term accepted in seconds (0 disables)
+    pub min_loan_duration: u64,
     pub risk_discount_factor: i128,
+    pub require_debtor_ack: bool,
+    pub risk_oracle: Option<Address>,
+    pub liquidation_escalation: i128,
+    pub max_liquidation_escalation: i128,
+    pub max_single_debtor_bps: i128,
+    pub accrual_after_due: AccrualAfterDue,
+    pub late_penalty_rate: i128,
+    /// LTV (bps) above which borrowers get an `ltv_warn` event; 0 disables
+    pub warning_threshold: i128,
+    /// One-time fee (bps of outstanding principal) added to interest when a loan goes overdue
+    pub late_fee_bps: i128,
+    /// Receivables issued less than this long ago can't be pledged (0 disables)
+    pub min_collateral_age_secs: u64,
+    /// Liquidation checks re-price collateral valued longer ago than this (0 disables)
+    pub max_staleness_secs: u64,
+    /// Longest interest-only period a borrower may request at origination (0 disables)
+    pub max_interest_only_secs: u64,
+    /// Receivables with an effective risk score above this can't be pledged
+    pub max_risk_score: u32,
+    /// Smallest partial repayment accepted, as an absolute amount (0 disables)
+    pub min_repay: i128,
+    /// Smallest partial repayment accepted, in bps of the amount owed (0 disables)
+    pub min_repay_bps: i128,
+    /// Accept receivables that are `Matured` (past due but unsettled) as collateral
+    pub allow_matured_collateral: bool,
+    /// Extra haircut (bps) on top of the risk discount for receivables past maturity
+    pub matured_haircut_bps: i128,
+    /// Max share (bps) of a basket's discounted value from any one receivable; 0 disables
+    pub max_single_collateral_bps: i128,
+    /// Breakage fee (bps of outstanding principal, scaled by remaining tenor) on early payoff; 0 disables
+    pub prepayment_fee_bps: i128,
+    /// Payoffs this close to `due_date` or later pay no prepayment fee
+    pub prepayment_free_window_secs: u64,
+    /// `get_loan` and the `loan` in `get_loan_live` carry interest projected to now instead of the stored snapshot
+    pub accrue_on_read: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Quote {
+    pub borrower: Address,
+    pub receivable_ids: Vec<u64>,
+    pub amount: i128,
+    pub duration: u64,
+    pub interest_rate: i128,
+    pub max_ltv: i128,
+    pub expires_ledger: u32,
+}
+
+/// What a basket can back right now; receivables over `max_risk_score` are
+/// listed in `excluded` and left out of both values
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BorrowCapacity {
+    pub collateral_value: i128,
+    pub max_borrow: i128,
+    pub excluded: Vec<u64>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct LoanView {
+    pub loan: Loan,
+    pub projected_interest: i128,
+    pub total_owed: i128,
+    pub prepayment_fee: i128,    // added on top of `total_owed` by a payoff now
+    pub current_ltv: i128,
+    pub health_factor: i128,     // bps, below 10000 = past liquidation threshold
+    pub is_liquidatable: bool,
+    pub seconds_to_due: u64,
+}
+
+/// Disbursement plan for a `borrow_tranched` loan; `next` indexes the next tranche to draw
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrancheSchedule {
+    pub tranches: Vec<i128>,
+    pub next: u32,
+}
+
+/// Lifetime borrowing profile of one address. `outstanding` is principal
+/// currently owed; assumed loans carry it over to the new borrower.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BorrowerStats {
+    pub loans_opened: u32,
+    pub repaid_on_time: u32,
+    pub repaid_late: u32,
+    pub liquidated: u32,
+    pub total_borrowed: i128,
+    pub interest_paid: i128,
+    pub outstanding: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct LoanDetail {
+    pub view: LoanView,
+    pub receivables: Vec<Receivable>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiquidationPreview {
+    pub loan_id: u64,
+    pub total_debt: i128,
+    pub penalty: i128,
+    pub receivable_ids: Vec<u64>,
+    pub receivable_values: Vec<i128>,
+    pub seized: Vec<u64>,        // receivables `liquidate` would hand over
+    pub seized_value: i128,      // their live discounted value
+    pub collateral_value: i128,
+    pub liquidatable_since: u64,
+    pub escalation: i128,
+    pub recovered: i128,
+    pub shortfall: i128,
 }
 
 #[contracttype]
@@ -52,6 +279,8 @@ pub enum ReceivableStatus {
     Matured,
     Settled,
     Defaulted,
+    Pending,
+    Disputed,
 }
 
 #[contracttype]
@@ -69,6 +298,25 @@ pub struct Receivable {
     pub status: ReceivableStatus,
     pub risk_score: u32,
     pub metadata_uri: soroban_sdk::String,
+    pub debtor_acknowledged: bool,
+    pub active_from: u64,
+}
+
+/// Independently pausable operations. `pause()` only stops new credit; the
+/// vault's own pause blocks `disburse` (so also borrowing) but not `repay`
+/// or `liq_recv`, so repayments and liquidations keep flowing through it.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PauseFlags {
+    pub borrow: bool,
+    pub repay: bool,
+    pub liquidate: bool,
+}
+
+enum PauseOp {
+    Borrow,
+    Repay,
+    Liquidate,
 }
 
 #[contracttype]
@@ -78,11 +326,24 @@ pub enum DataKey {
     VaultContract,
     Config,
     NextLoanId,
-    Loan(u64),
+    Loan(u64),           // legacy layout (LegacyLoan), read-only
     BorrowerLoans(Address),
     TotalLoans,
     TotalBorrowed,
-    Paused,
+    Paused,              // legacy single flag: borrow pause until PauseFlags is first set
+    PauseFlags,
+    NextQuoteId,
+    Quote(u64),          // temporary storage, expires with the quote
+    LoanV2(u64),
+    Tranches(u64),
+    BorrowerStats(Address),
+    RecoveryAddress,     // receives collateral seized by `force_liquidate`
+    CollateralFactor(Address), // per-currency LTV cap (bps), overrides `max_ltv`
+    OpenLoans,           // ids of active loans in origination order
+    LoanDebtors(u64),    // collateral value per debtor_hash, the loan's exposure weights
+    LoanExposure(u64),   // exposure per debtor_hash this loan currently contributes
+    DebtorExposure(BytesN<32>), // outstanding principal apportioned to a debtor, book-wide
+    CollateralRecipient(u64), // receives the loan's collateral on full repayment instead of the borrower
 }
 
 #[contracterror]
@@ -103,9 +364,49 @@ pub enum Error {
     RecvNotActive = 12,
     Overflow = 13,
     NotBorrower = 14,
+    RecvNotAcknowledged = 15,
+    QuoteNotFound = 16,
+    QuoteExpired = 17,
+    QuoteMismatch = 18,
+    TooManyReceivables = 19,
+    LoanUnhealthy = 20,
+    DebtorConcentration = 21,
+    VaultCannotDisburse = 22,
+    InvalidTranches = 23,
+    NoTrancheRemaining = 24,
+    InvalidConfig = 25,
+    VaultLiquidity = 26,
+    VaultPaused = 27,
+    VaultUtilization = 28,
+    RecvError = 29,
+    VaultRejected = 30,
+    NoRecoveryAddress = 31,
+    CollateralTooNew = 32,
+    InterestOnlyPeriod = 33,
+    RiskTooHigh = 34,
+    RepayTooSmall = 35,
+    CollateralConcentration = 36,
+    SlippageExceeded = 37,
+    InvalidRepayAmount = 38,
+    Miswired = 39,
+    NotInLoan = 40,
+    CurrencyMismatch = 41,
 }
 
 const SECONDS_PER_YEAR: u64 = 31_557_600;
+const QUOTE_TTL_LEDGERS: u32 = 300;
+/// Assumed ledger close time, used to turn a loan's remaining term into a TTL
+const SECS_PER_LEDGER: u64 = 5;
+/// How long (~30 days of ledgers) loan entries outlive `due_date`, so overdue
+/// loans can still be repaid or liquidated
+const LOAN_TTL_MARGIN_LEDGERS: u32 = 518_400;
+/// A warned loan's LTV must fall this far (bps) below `warning_threshold` to clear the flag
+const LTV_WARN_HYSTERESIS: i128 = 200;
+/// Largest collateral basket per loan. Keeps the per-receivable cross-contract
+/// reads in `get_loan_detail`, liquidation and refresh within the read budget.
+pub const MAX_LOAN_RECEIVABLES: u32 = 20;
+/// Most loan ids `liquidatable_loans` checks per call; each costs a few reads
+pub const MAX_LIQUIDATION_SCAN: u32 = 50;
 
 #[contract]
 pub struct BorrowContract;
@@ -124,6 +425,7 @@ impl BorrowContract {
             return Err(Error::AlreadyInitialized);
         }
         admin.require_auth();
+        Self::validate_config(&config)?;
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::RecvContract, &recv_contract);
         env.storage().instance().set(&DataKey::VaultContract, &vault_contract);
@@ -131,17 +433,76 @@ impl BorrowContract {
         env.storage().instance().set(&DataKey::NextLoanId, &1u64);
         env.storage().instance().set(&DataKey::TotalLoans, &0u64);
         env.storage().instance().set(&DataKey::TotalBorrowed, &0i128);
-        env.storage().instance().set(&DataKey::Paused, &false);
+        env.storage().instance().set(&DataKey::PauseFlags, &PauseFlags::default());
         Ok(())
     }
 
     pub fn set_config(env: Env, config: BorrowConfig) -> Result<(), Error> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
+        Self::validate_config(&config)?;
         env.storage().instance().set(&DataKey::Config, &config);
         Ok(())
     }
 
+    /// Point this pool at `recv_contract` and `vault_contract` and authorize it
+    /// on both in one transaction. All three must share the same admin. Fails
+    /// with `Miswired` unless both now name this contract as their borrow
+    /// contract and, when the receivable contract pins a base currency, it is
+    /// the vault's asset.
+    pub fn configure(env: Env, recv_contract: Address, vault_contract: Address) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let this = env.current_contract_address();
+
+        let _: () = env.invoke_contract(
+            &recv_contract,
+            &Symbol::new(&env, "add_borrow"),
+            soroban_sdk::vec![&env, this.clone().into_val(&env)],
+        );
+        let _: () = env.invoke_contract(
+            &vault_contract,
+            &Symbol::new(&env, "set_borrow"),
+            soroban_sdk::vec![&env, this.clone().into_val(&env)],
+        );
+
+        let recv_ok: bool = env.invoke_contract(
+            &recv_contract,
+            &Symbol::new(&env, "is_borrow"),
+            soroban_sdk::vec![&env, this.clone().into_val(&env)],
+        );
+        let vault_borrow: Option<Address> = env.invoke_contract(
+            &vault_contract,
+            &Symbol::new(&env, "get_borrow"),
+            soroban_sdk::vec![&env],
+        );
+        let base: Option<Address> = env.invoke_contract(
+            &recv_contract,
+            &Symbol::new(&env, "base_currency"),
+            soroban_sdk::vec![&env],
+        );
+        let asset: Address = env.invoke_contract(
+            &vault_contract,
+            &Symbol::new(&env, "base_asset"),
+            soroban_sdk::vec![&env],
+        );
+        if !recv_ok || vault_borrow != Some(this) || base.is_some_and(|b| b != asset) {
+            return Err(Error::Miswired);
+        }
+
+        env.storage().instance().set(&DataKey::RecvContract, &recv_contract);
+        env.storage().instance().set(&DataKey::VaultContract, &vault_contract);
+        Ok(())
+    }
+
+    /// `(receivable contract, vault contract)` this pool talks to
+    pub fn get_contracts(env: Env) -> (Address, Address) {
+        (
+            env.storage().instance().get(&DataKey::RecvContract).unwrap(),
+            env.storage().instance().get(&DataKey::VaultContract).unwrap(),
+        )
+    }
+
     // ========================================================================
     // Borrow
     // ========================================================================
@@ -152,56 +513,235 @@ impl BorrowContract {
         receivable_ids: Vec<u64>,
         borrow_amount: i128,
         duration: u64,
+        quote_id: Option<u64>,
+        metadata_uri: Option<String>,
+        external_ref: Option<BytesN<32>>,
+        interest_only_secs: Option<u64>,
+        collateral_recipient: Option<Address>,
     ) -> Result<u64, Error> {
-        Self::require_not_paused(&env)?;
+        Self::require_not_paused(&env, PauseOp::Borrow)?;
         borrower.require_auth();
         if borrow_amount <= 0 { return Err(Error::ZeroAmount); }
 
         let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
-        if duration == 0 || duration > config.max_loan_duration {
+        if duration == 0 || duration < config.min_loan_duration || duration > config.max_loan_duration {
+            return Err(Error::InvalidDuration);
+        }
+        let interest_only_secs = interest_only_secs.unwrap_or(0);
+        if interest_only_secs > config.max_interest_only_secs || interest_only_secs > duration {
+            return Err(Error::InvalidDuration);
+        }
+
+        // A valid quote pins the rate and LTV cap it was issued with
+        let (interest_rate, max_ltv) = match quote_id {
+            Some(qid) => {
+                let quote: Quote = env.storage().temporary().get(&DataKey::Quote(qid))
+                    .ok_or(Error::QuoteNotFound)?;
+                if env.ledger().sequence() > quote.expires_ledger { return Err(Error::QuoteExpired); }
+                if quote.borrower != borrower
+                    || quote.receivable_ids != receivable_ids
+                    || quote.amount != borrow_amount
+                    || quote.duration != duration
+                {
+                    return Err(Error::QuoteMismatch);
+                }
+                env.storage().temporary().remove(&DataKey::Quote(qid));
+                (quote.interest_rate, quote.max_ltv)
+            }
+            None => (config.base_interest_rate, config.max_ltv),
+        };
+
+        let loan_id = Self::open_loan(
+            &env, &config, &borrower, &receivable_ids, borrow_amount, borrow_amount,
+            duration, interest_rate, max_ltv, metadata_uri, external_ref,
+        )?;
+        if interest_only_secs > 0 {
+            let mut loan = Self::get_internal(&env, loan_id)?;
+            loan.interest_only_until = loan.borrowed_at + interest_only_secs;
+            Self::put_loan(&env, &loan);
+        }
+        if let Some(recipient) = collateral_recipient {
+            env.storage().persistent().set(&DataKey::CollateralRecipient(loan_id), &recipient);
+        }
+        Ok(loan_id)
+    }
+
+    /// Zero-coupon loan: disburse `proceeds` now against a fixed `repay_amount`
+    /// owed at maturity. The discount is booked as interest at origination,
+    /// so what is owed doesn't depend on when accrual runs; `interest_rate`
+    /// only records the implied simple annual rate. `repay_amount` is the debt
+    /// checked against `max_ltv` and used by liquidation.
+    pub fn borrow_discount(
+        env: Env,
+        borrower: Address,
+        receivable_ids: Vec<u64>,
+        proceeds: i128,
+        repay_amount: i128,
+        duration: u64,
+    ) -> Result<u64, Error> {
+        Self::require_not_paused(&env, PauseOp::Borrow)?;
+        borrower.require_auth();
+        if proceeds <= 0 { return Err(Error::ZeroAmount); }
+        if repay_amount < proceeds { return Err(Error::InvalidRepayAmount); }
+
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        if duration == 0 || duration < config.min_loan_duration || duration > config.max_loan_duration {
             return Err(Error::InvalidDuration);
         }
 
-        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
-        let vault_addr: Address = env.storage().instance().get(&DataKey::VaultContract).unwrap();
+        let discount = repay_amount - proceeds;
+        let implied_rate = Self::mul_div(
+            Self::mul_div(discount, 10000, proceeds)?,
+            SECONDS_PER_YEAR as i128,
+            duration as i128,
+        )?;
+        let loan_id = Self::open_loan(
+            &env, &config, &borrower, &receivable_ids, repay_amount, proceeds,
+            duration, implied_rate, config.max_ltv, None, None,
+        )?;
 
-        // Validate receivables and compute discounted collateral
-        let mut total_collateral: i128 = 0;
-        for rid in receivable_ids.iter() {
-            let recv: Receivable = env.invoke_contract(
-                &recv_addr,
-                &Symbol::new(&env, "get_recv"),
-                soroban_sdk::vec![&env, rid.into_val(&env)],
-            );
-            if recv.owner != borrower { return Err(Error::RecvNotOwned); }
-            if recv.status != ReceivableStatus::Active { return Err(Error::RecvNotActive); }
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        loan.accrued_interest = discount;
+        loan.fixed_repayment = repay_amount;
+        Self::put_loan(&env, &loan);
+        Ok(loan_id)
+    }
 
-            let risk_disc = Self::mul_div(recv.risk_score as i128, config.risk_discount_factor, 10000)?;
-            let eff = 10000i128.saturating_sub(risk_disc);
-            let disc_val = Self::mul_div(recv.face_value, eff, 10000)?;
-            total_collateral = total_collateral.checked_add(disc_val).ok_or(Error::Overflow)?;
+    /// Approve a `total_amount` credit line against the receivables but
+    /// disburse only `tranches[0]`; `draw` releases the rest in order.
+    /// Interest accrues only on what has been drawn.
+    pub fn borrow_tranched(
+        env: Env,
+        borrower: Address,
+        receivable_ids: Vec<u64>,
+        total_amount: i128,
+        tranches: Vec<i128>,
+        duration: u64,
+    ) -> Result<u64, Error> {
+        Self::require_not_paused(&env, PauseOp::Borrow)?;
+        borrower.require_auth();
+        if total_amount <= 0 { return Err(Error::ZeroAmount); }
+
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        if duration == 0 || duration < config.min_loan_duration || duration > config.max_loan_duration {
+            return Err(Error::InvalidDuration);
+        }
+
+        let mut sum: i128 = 0;
+        for t in tranches.iter() {
+            if t <= 0 { return Err(Error::InvalidTranches); }
+            sum = sum.checked_add(t).ok_or(Error::Overflow)?;
+        }
+        if tranches.is_empty() || sum != total_amount { return Err(Error::InvalidTranches); }
+
+        let first = tranches.get(0).unwrap();
+        let loan_id = Self::open_loan(
+            &env, &config, &borrower, &receivable_ids, total_amount, first,
+            duration, config.base_interest_rate, config.max_ltv, None, None,
+        )?;
+        env.storage().persistent().set(&DataKey::Tranches(loan_id), &TrancheSchedule { tranches, next: 1 });
+        Ok(loan_id)
+    }
+
+    /// Release the next tranche of a `borrow_tranched` loan. Fails if the
+    /// loan is overdue or the new debt would breach `max_ltv`.
+    pub fn draw(env: Env, borrower: Address, loan_id: u64) -> Result<i128, Error> {
+        Self::require_not_paused(&env, PauseOp::Borrow)?;
+        borrower.require_auth();
+
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+        if loan.borrower != borrower { return Err(Error::NotBorrower); }
+        if env.ledger().timestamp() > loan.due_date { return Err(Error::LoanUnhealthy); }
+
+        let mut schedule: TrancheSchedule = env.storage().persistent()
+            .get(&DataKey::Tranches(loan_id))
+            .ok_or(Error::NoTrancheRemaining)?;
+        let amount = schedule.tranches.get(schedule.next).ok_or(Error::NoTrancheRemaining)?;
+
+        Self::accrue(&env, &mut loan)?;
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        let new_debt = loan.principal
+            .checked_add(loan.accrued_interest).ok_or(Error::Overflow)?
+            .checked_add(amount).ok_or(Error::Overflow)?;
+        if new_debt > Self::mul_div(loan.collateral_value, loan.terms.max_ltv, 10000)? {
+            return Err(Error::LTVExceeded);
         }
 
-        // LTV check
-        let max_borrow = Self::mul_div(total_collateral, config.max_ltv, 10000)?;
-        if borrow_amount > max_borrow { return Err(Error::LTVExceeded); }
+        Self::call_vault(
+            &env,
+            "preflight_disburse",
+            soroban_sdk::vec![&env, amount.into_val(&env)],
+            Error::VaultCannotDisburse,
+        )?;
+        Self::call_vault(
+            &env,
+            "disburse",
+            soroban_sdk::vec![&env, borrower.clone().into_val(&env), amount.into_val(&env)],
+            Error::VaultCannotDisburse,
+        )?;
+
+        loan.principal = loan.principal.checked_add(amount).ok_or(Error::Overflow)?;
+        Self::track_health(&env, &mut loan, &config)?;
+        Self::put_loan(&env, &loan);
+        Self::update_stats(&env, &borrower, |st| {
+            st.total_borrowed += amount;
+            st.outstanding += amount;
+        });
+
+        schedule.next += 1;
+        env.storage().persistent().set(&DataKey::Tranches(loan_id), &schedule);
+        let tb: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap();
+        env.storage().instance().set(&DataKey::TotalBorrowed, &(tb + amount));
+
+        env.events().publish((symbol_short!("draw"), borrower), (loan_id, amount, schedule.next));
+        Ok(amount)
+    }
+
+    pub fn get_tranches(env: Env, loan_id: u64) -> Option<TrancheSchedule> {
+        env.storage().persistent().get(&DataKey::Tranches(loan_id))
+    }
+
+    /// Validate and lock collateral for a `credit_line`, disburse
+    /// `first_draw` of it and record the loan.
+    fn open_loan(
+        env: &Env,
+        config: &BorrowConfig,
+        borrower: &Address,
+        receivable_ids: &Vec<u64>,
+        credit_line: i128,
+        first_draw: i128,
+        duration: u64,
+        interest_rate: i128,
+        max_ltv: i128,
+        metadata_uri: Option<String>,
+        external_ref: Option<BytesN<32>>,
+    ) -> Result<u64, Error> {
+        // Validate receivables, compute discounted collateral and what it can back
+        let (total_collateral, max_borrow, per_debtor) = Self::pledge_value(env, config, borrower, receivable_ids, max_ltv, None, &Vec::new(env))?;
+        if credit_line > max_borrow { return Err(Error::LTVExceeded); }
+
+        // Dry-run the vault's checks so nothing is locked for a borrow it would reject
+        Self::call_vault(
+            env,
+            "preflight_disburse",
+            soroban_sdk::vec![env, first_draw.into_val(env)],
+            Error::VaultCannotDisburse,
+        )?;
 
         // Lock receivables (pass our own address for multi-pool auth)
         let self_addr = env.current_contract_address();
         for rid in receivable_ids.iter() {
-            let _: () = env.invoke_contract(
-                &recv_addr,
-                &Symbol::new(&env, "lock"),
-                soroban_sdk::vec![&env, rid.into_val(&env), self_addr.clone().into_val(&env)],
-            );
+            Self::call_recv(env, "lock", soroban_sdk::vec![env, rid.into_val(env), self_addr.clone().into_val(env)])?;
         }
 
         // Disburse from vault
-        let _: () = env.invoke_contract(
-            &vault_addr,
-            &Symbol::new(&env, "disburse"),
-            soroban_sdk::vec![&env, borrower.clone().into_val(&env), borrow_amount.into_val(&env)],
-        );
+        Self::call_vault(
+            env,
+            "disburse",
+            soroban_sdk::vec![env, borrower.clone().into_val(env), first_draw.into_val(env)],
+            Error::VaultCannotDisburse,
+        )?;
 
         // Create loan
         let loan_id: u64 = env.storage().instance().get(&DataKey::NextLoanId).unwrap();
@@ -213,31 +753,115 @@ impl BorrowContract {
             borrower: borrower.clone(),
             receivable_ids: receivable_ids.clone(),
             collateral_value: total_collateral,
-            principal: borrow_amount,
-            interest_rate: config.base_interest_rate,
+            principal: first_draw,
+            interest_rate,
             accrued_interest: 0,
             borrowed_at: now,
             last_interest_update: now,
             due_date: now + duration,
             status: LoanStatus::Active,
+            liquidatable_since: 0,
+            metadata_uri,
+            external_ref: external_ref.unwrap_or(BytesN::from_array(env, &[0; 32])),
+            warned: false,
+            interest_only_until: 0,
+            collateral_refreshed_at: now,
+            fixed_repayment: 0,
+            terms: LoanTerms {
+                max_ltv: if total_collateral > 0 { Self::mul_div(max_borrow, 10000, total_collateral)? } else { max_ltv },
+                ..LoanTerms::from_config(config)
+            },
         };
-        env.storage().persistent().set(&DataKey::Loan(loan_id), &loan);
+        let mut open: Vec<u64> = env.storage().persistent().get(&DataKey::OpenLoans).unwrap_or(Vec::new(env));
+        open.push_back(loan_id);
+        env.storage().persistent().set(&DataKey::OpenLoans, &open);
 
         let mut blist: Vec<u64> = env.storage().persistent()
             .get(&DataKey::BorrowerLoans(borrower.clone()))
-            .unwrap_or(Vec::new(&env));
+            .unwrap_or(Vec::new(env));
         blist.push_back(loan_id);
         env.storage().persistent().set(&DataKey::BorrowerLoans(borrower.clone()), &blist);
 
         let tl: u64 = env.storage().instance().get(&DataKey::TotalLoans).unwrap();
         env.storage().instance().set(&DataKey::TotalLoans, &(tl + 1));
         let tb: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap();
-        env.storage().instance().set(&DataKey::TotalBorrowed, &(tb + borrow_amount));
+        env.storage().instance().set(&DataKey::TotalBorrowed, &(tb + first_draw));
+        Self::update_stats(env, borrower, |st| {
+            st.loans_opened += 1;
+            st.total_borrowed += first_draw;
+            st.outstanding += first_draw;
+        });
+        env.storage().persistent().set(&DataKey::LoanDebtors(loan_id), &per_debtor);
+        // Stored last so the exposure sync and TTL bump see the entries written above
+        Self::put_loan(env, &loan);
 
-        env.events().publish((symbol_short!("borrow"), borrower), (loan_id, borrow_amount));
+        env.events().publish(
+            (symbol_short!("borrow"), borrower.clone()),
+            (loan_id, first_draw, loan.metadata_uri, loan.external_ref),
+        );
         Ok(loan_id)
     }
 
+    /// Reserve the current rate and LTV cap for `QUOTE_TTL_LEDGERS` ledgers.
+    /// Validates the request like `borrow` would but does not lock receivables
+    /// or reserve vault liquidity.
+    pub fn lock_quote(
+        env: Env,
+        borrower: Address,
+        receivable_ids: Vec<u64>,
+        amount: i128,
+        duration: u64,
+    ) -> Result<u64, Error> {
+        Self::require_not_paused(&env, PauseOp::Borrow)?;
+        borrower.require_auth();
+        if amount <= 0 { return Err(Error::ZeroAmount); }
+
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        if duration == 0 || duration < config.min_loan_duration || duration > config.max_loan_duration {
+            return Err(Error::InvalidDuration);
+        }
+
+        let (_, max_borrow, _) = Self::pledge_value(&env, &config, &borrower, &receivable_ids, config.max_ltv, None, &Vec::new(&env))?;
+        if amount > max_borrow { return Err(Error::LTVExceeded); }
+
+        let quote_id: u64 = env.storage().instance().get(&DataKey::NextQuoteId).unwrap_or(1);
+        env.storage().instance().set(&DataKey::NextQuoteId, &(quote_id + 1));
+
+        let quote = Quote {
+            borrower: borrower.clone(),
+            receivable_ids,
+            amount,
+            duration,
+            interest_rate: config.base_interest_rate,
+            max_ltv: config.max_ltv,
+            expires_ledger: env.ledger().sequence() + QUOTE_TTL_LEDGERS,
+        };
+        let key = DataKey::Quote(quote_id);
+        env.storage().temporary().set(&key, &quote);
+        // Outlive the expiry so a late borrow gets QuoteExpired rather than a missing entry
+        env.storage().temporary().extend_ttl(&key, 2 * QUOTE_TTL_LEDGERS, 2 * QUOTE_TTL_LEDGERS);
+
+        env.events().publish((symbol_short!("quote"), borrower), (quote_id, quote.interest_rate));
+        Ok(quote_id)
+    }
+
+    pub fn get_quote(env: Env, quote_id: u64) -> Option<Quote> {
+        env.storage().temporary().get(&DataKey::Quote(quote_id))
+    }
+
+    /// How much `borrow` would lend against the basket at the current config.
+    /// Receivables over `max_risk_score` are dropped from the quote and
+    /// returned in `excluded` instead of failing it; any other pledging
+    /// problem still fails as it would in `borrow`.
+    pub fn max_borrowable(env: Env, borrower: Address, receivable_ids: Vec<u64>) -> Result<BorrowCapacity, Error> {
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        let mut excluded = Vec::new(&env);
+        let (collateral_value, max_borrow, _) = Self::pledge_value(
+            &env, &config, &borrower, &receivable_ids, config.max_ltv, Some(&mut excluded), &Vec::new(&env),
+        )?;
+        Ok(BorrowCapacity { collateral_value, max_borrow, excluded })
+    }
+
     // ========================================================================
     // Repayment
     // ========================================================================
@@ -248,7 +872,7 @@ impl BorrowContract {
         loan_id: u64,
         amount: i128,
     ) -> Result<i128, Error> {
-        Self::require_not_paused(&env)?;
+        Self::require_not_paused(&env, PauseOp::Repay)?;
         borrower.require_auth();
         if amount <= 0 { return Err(Error::ZeroAmount); }
 
@@ -257,119 +881,516 @@ impl BorrowContract {
         if loan.borrower != borrower { return Err(Error::NotBorrower); }
 
         Self::accrue(&env, &mut loan)?;
-
-        let total_owed = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
-        let payment = core::cmp::min(amount, total_owed);
-
-        let interest_pay = core::cmp::min(payment, loan.accrued_interest);
-        let principal_pay = payment.checked_sub(interest_pay).ok_or(Error::Overflow)?;
+        Self::charge_prepayment_fee(&env, &mut loan, amount)?;
+        let (payment, principal_pay, interest_pay) = Self::split_payment(&env, &loan, amount)?;
 
         // Forward to vault
-        let vault_addr: Address = env.storage().instance().get(&DataKey::VaultContract).unwrap();
-        let _: () = env.invoke_contract(
-            &vault_addr,
-            &Symbol::new(&env, "repay"),
+        Self::call_vault(
+            &env,
+            "repay",
             soroban_sdk::vec![
                 &env,
                 borrower.clone().into_val(&env),
                 principal_pay.into_val(&env),
                 interest_pay.into_val(&env),
             ],
-        );
-
-        loan.principal = loan.principal.checked_sub(principal_pay).ok_or(Error::Overflow)?;
-        loan.accrued_interest = loan.accrued_interest.checked_sub(interest_pay).ok_or(Error::Overflow)?;
-
-        let remaining = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
-        if remaining == 0 {
-            loan.status = LoanStatus::Repaid;
-
-            // Unlock receivables (pass our own address for multi-pool auth)
-            let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
-            let self_addr = env.current_contract_address();
-            for rid in loan.receivable_ids.iter() {
-                let _: () = env.invoke_contract(
-                    &recv_addr,
-                    &Symbol::new(&env, "unlock"),
-                    soroban_sdk::vec![&env, rid.into_val(&env), self_addr.clone().into_val(&env)],
-                );
-            }
-        }
+            Error::VaultRejected,
+        )?;
 
-        env.storage().persistent().set(&DataKey::Loan(loan_id), &loan);
+        let remaining = Self::apply_repayment(&env, &mut loan, principal_pay, interest_pay)?;
         env.events().publish((symbol_short!("repay"), borrower), (loan_id, payment, remaining));
         Ok(remaining)
     }
 
-    // ========================================================================
-    // Liquidation
-    // ========================================================================
-
-    pub fn liquidate(
+    /// Repay by netting against the borrower's own LP position: the vault
+    /// burns just enough of `shares` to cover the payment and books it as a
+    /// repayment, so no tokens leave or enter the vault.
+    pub fn repay_from_shares(
         env: Env,
-        liquidator: Address,
+        borrower: Address,
         loan_id: u64,
-    ) -> Result<(), Error> {
-        Self::require_not_paused(&env)?;
-        liquidator.require_auth();
+        shares: i128,
+    ) -> Result<i128, Error> {
+        Self::require_not_paused(&env, PauseOp::Repay)?;
+        borrower.require_auth();
+        if shares <= 0 { return Err(Error::ZeroAmount); }
 
         let mut loan = Self::get_internal(&env, loan_id)?;
         if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+        if loan.borrower != borrower { return Err(Error::NotBorrower); }
 
         Self::accrue(&env, &mut loan)?;
 
-        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
-        let now = env.ledger().timestamp();
+        let vault_addr: Address = env.storage().instance().get(&DataKey::VaultContract).unwrap();
+        let value: i128 = env.invoke_contract(
+            &vault_addr,
+            &Symbol::new(&env, "shares_value"),
+            soroban_sdk::vec![&env, shares.into_val(&env)],
+        );
+        if value <= 0 { return Err(Error::ZeroAmount); }
+        Self::charge_prepayment_fee(&env, &mut loan, value)?;
+        let (payment, principal_pay, interest_pay) = Self::split_payment(&env, &loan, value)?;
 
-        let total_debt = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
-        let current_ltv = Self::mul_div(total_debt, 10000, loan.collateral_value)?;
+        let burned: i128 = env.invoke_contract(
+            &vault_addr,
+            &Symbol::new(&env, "repay_from_shares"),
+            soroban_sdk::vec![
+                &env,
+                borrower.clone().into_val(&env),
+                principal_pay.into_val(&env),
+                interest_pay.into_val(&env),
+            ],
+        );
 
-        let is_underwater = current_ltv > config.liquidation_threshold;
-        let is_overdue = now > loan.due_date;
+        let remaining = Self::apply_repayment(&env, &mut loan, principal_pay, interest_pay)?;
+        env.events().publish((symbol_short!("repay_shr"), borrower), (loan_id, payment, remaining, burned));
+        Ok(remaining)
+    }
 
-        if !is_underwater && !is_overdue { return Err(Error::NotLiquidatable); }
+    /// Auto-settlement entry point for the receivable contract's
+    /// `settle_with_payment`: the debtor paid `amount` of `currency` on
+    /// `receivable_id`, which `owner` has pledged here, and the receivable
+    /// contract has already moved the money to this contract. It repays the
+    /// loan holding the receivable, interest first, and any excess goes to
+    /// the borrower. `min_repay`, the interest-only window and the prepayment
+    /// fee don't apply, as the debtor chose the timing, not the borrower.
+    /// The payment reaches the vault in the base asset even when it takes
+    /// loan interest in a fee token, so its interest is split between LPs and
+    /// reserves as usual.
+    /// Returns the receivables to unlock: the whole basket once the loan is
+    /// repaid, else none, and the loan's collateral recipient. The receivable
+    /// contract releases them (to the recipient, if set) itself, since it
+    /// can't be called back mid-payment.
+    pub fn on_recv_payment(
+        env: Env,
+        receivable_id: u64,
+        owner: Address,
+        currency: Address,
+        amount: i128,
+    ) -> Result<(Vec<u64>, Option<Address>), Error> {
+        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
+        recv_addr.require_auth();
+        Self::require_not_paused(&env, PauseOp::Repay)?;
+        if amount <= 0 { return Err(Error::ZeroAmount); }
 
-        let penalty = Self::mul_div(total_debt, config.liquidation_penalty, 10000)?;
-        let liq_value = total_debt.checked_add(penalty).ok_or(Error::Overflow)?;
-        let recovered = core::cmp::min(loan.collateral_value, liq_value);
-        let shortfall = total_debt.saturating_sub(recovered);
+        let vault_addr: Address = env.storage().instance().get(&DataKey::VaultContract).unwrap();
+        let base_asset: Address = env.invoke_contract(&vault_addr, &Symbol::new(&env, "base_asset"), Vec::new(&env));
+        if currency != base_asset { return Err(Error::CurrencyMismatch); }
 
-        // Transfer receivables to liquidator
-        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
-        let self_addr = env.current_contract_address();
-        for rid in loan.receivable_ids.iter() {
-            let _: () = env.invoke_contract(
-                &recv_addr,
-                &Symbol::new(&env, "unlock"),
-                soroban_sdk::vec![&env, rid.into_val(&env), self_addr.clone().into_val(&env)],
-            );
-            let _: () = env.invoke_contract(
-                &recv_addr,
-                &Symbol::new(&env, "transfer"),
-                soroban_sdk::vec![
+        let loan_ids: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::BorrowerLoans(owner))
+            .unwrap_or(Vec::new(&env));
+        let mut loan = loan_ids.iter()
+            .filter_map(|lid| Self::get_internal(&env, lid).ok())
+            .find(|l| l.status == LoanStatus::Active && l.receivable_ids.contains(receivable_id))
+            .ok_or(Error::NotInLoan)?;
+
+        Self::accrue(&env, &mut loan)?;
+        let total_owed = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        let payment = core::cmp::min(amount, total_owed);
+        let interest_pay = core::cmp::min(payment, loan.accrued_interest);
+        let principal_pay = payment - interest_pay;
+
+        // The vault pulls the payment from this contract, one call down
+        let self_addr = env.current_contract_address();
+        if payment > 0 {
+            env.authorize_as_current_contract(soroban_sdk::vec![
+                &env,
+                InvokerContractAuthEntry::Contract(SubContractInvocation {
+                    context: ContractContext {
+                        contract: base_asset.clone(),
+                        fn_name: Symbol::new(&env, "transfer"),
+                        args: soroban_sdk::vec![
+                            &env,
+                            self_addr.clone().into_val(&env),
+                            vault_addr.into_val(&env),
+                            payment.into_val(&env),
+                        ],
+                    },
+                    sub_invocations: Vec::new(&env),
+                }),
+            ]);
+            Self::call_vault(
+                &env,
+                "repay",
+                soroban_sdk::vec![
                     &env,
-                    rid.into_val(&env),
-                    loan.borrower.clone().into_val(&env),
-                    liquidator.clone().into_val(&env),
+                    self_addr.clone().into_val(&env),
+                    principal_pay.into_val(&env),
+                    interest_pay.into_val(&env),
                 ],
-            );
+                Error::VaultRejected,
+            )?;
+        }
+        if amount > payment {
+            token::Client::new(&env, &base_asset).transfer(&self_addr, &loan.borrower, &(amount - payment));
         }
 
-        // Notify vault
-        let vault_addr: Address = env.storage().instance().get(&DataKey::VaultContract).unwrap();
-        let _: () = env.invoke_contract(
-            &vault_addr,
-            &Symbol::new(&env, "liq_recv"),
-            soroban_sdk::vec![&env, recovered.into_val(&env), shortfall.into_val(&env)],
-        );
+        let remaining = Self::book_repayment(&env, &mut loan, principal_pay, interest_pay)?;
+        env.events().publish((symbol_short!("recv_pay"), loan.id), (receivable_id, payment, remaining));
+        if remaining > 0 { return Ok((Vec::new(&env), None)); }
+        let recipient = env.storage().persistent().get(&DataKey::CollateralRecipient(loan.id));
+        Ok((loan.receivable_ids, recipient))
+    }
 
-        loan.status = LoanStatus::Liquidated;
-        env.storage().persistent().set(&DataKey::Loan(loan_id), &loan);
+    /// Breakage fee for closing the loan now: `prepayment_fee_bps` of the
+    /// outstanding principal, scaled by the share of the tenor still to run.
+    /// Nothing inside the free window before maturity, and nothing on a
+    /// zero-coupon loan, which owes its full discount regardless.
+    fn prepayment_fee(env: &Env, loan: &Loan) -> Result<i128, Error> {
+        let now = env.ledger().timestamp();
+        let terms = &loan.terms;
+        if terms.prepayment_fee_bps == 0 || loan.fixed_repayment > 0 { return Ok(0); }
+        let remaining = loan.due_date.saturating_sub(now);
+        if remaining <= terms.prepayment_free_window_secs { return Ok(0); }
+
+        let fee = Self::mul_div(loan.principal, terms.prepayment_fee_bps, 10000)?;
+        Self::mul_div(fee, remaining as i128, (loan.due_date - loan.borrowed_at) as i128)
+    }
+
+    /// A payment that reaches the (accrued) balance is a payoff, so the
+    /// prepayment fee is added to the interest it has to cover. One that
+    /// clears the balance but not the fee fails with `RepayTooSmall` rather
+    /// than closing the loan fee-free.
+    fn charge_prepayment_fee(env: &Env, loan: &mut Loan, amount: i128) -> Result<(), Error> {
+        let total_owed = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        if amount < total_owed { return Ok(()); }
+        let fee = Self::prepayment_fee(env, loan)?;
+        if amount < total_owed.checked_add(fee).ok_or(Error::Overflow)? { return Err(Error::RepayTooSmall); }
+        loan.accrued_interest = loan.accrued_interest.checked_add(fee).ok_or(Error::Overflow)?;
+        Ok(())
+    }
+
+    /// Cap `amount` at what the (accrued) loan owes; returns (payment, principal, interest).
+    /// Interest is paid first. Inside the interest-only window a partial
+    /// payment is capped at accrued interest; paying off the whole loan is still allowed.
+    /// Partial payments below the configured minimum fail with `RepayTooSmall`,
+    /// so dust payments can't be used to churn accrual.
+    fn split_payment(env: &Env, loan: &Loan, amount: i128) -> Result<(i128, i128, i128), Error> {
+        let total_owed = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        if amount < total_owed {
+            let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+            let min = core::cmp::max(config.min_repay, Self::mul_div(total_owed, config.min_repay_bps, 10000)?);
+            if amount < min { return Err(Error::RepayTooSmall); }
+        }
+        let mut payment = core::cmp::min(amount, total_owed);
+        if payment < total_owed && env.ledger().timestamp() < loan.interest_only_until {
+            payment = core::cmp::min(payment, loan.accrued_interest);
+            if payment == 0 { return Err(Error::InterestOnlyPeriod); }
+        }
+
+        let interest_pay = core::cmp::min(payment, loan.accrued_interest);
+        let principal_pay = payment.checked_sub(interest_pay).ok_or(Error::Overflow)?;
+        Ok((payment, principal_pay, interest_pay))
+    }
+
+    /// Book a payment the vault has already accepted; closes the loan and
+    /// unlocks its collateral when nothing is left, handing it to the loan's
+    /// collateral recipient first if one was set. Returns the remaining debt.
+    fn apply_repayment(env: &Env, loan: &mut Loan, principal_pay: i128, interest_pay: i128) -> Result<i128, Error> {
+        let remaining = Self::book_repayment(env, loan, principal_pay, interest_pay)?;
+        if remaining == 0 {
+            // Unlock receivables (pass our own address for multi-pool auth)
+            let self_addr = env.current_contract_address();
+            let recipient: Option<Address> = env.storage().persistent().get(&DataKey::CollateralRecipient(loan.id));
+            for rid in loan.receivable_ids.iter() {
+                if let Some(to) = &recipient {
+                    Self::call_recv(env, "force_transfer", soroban_sdk::vec![
+                        env,
+                        rid.into_val(env),
+                        to.into_val(env),
+                        self_addr.clone().into_val(env),
+                    ])?;
+                }
+                Self::call_recv(env, "unlock", soroban_sdk::vec![env, rid.into_val(env), self_addr.clone().into_val(env)])?;
+            }
+        }
+        Ok(remaining)
+    }
+
+    /// `apply_repayment` without unlocking: a repaid loan is closed but its
+    /// collateral is left for the caller to release
+    fn book_repayment(env: &Env, loan: &mut Loan, principal_pay: i128, interest_pay: i128) -> Result<i128, Error> {
+        loan.principal = loan.principal.checked_sub(principal_pay).ok_or(Error::Overflow)?;
+        loan.accrued_interest = loan.accrued_interest.checked_sub(interest_pay).ok_or(Error::Overflow)?;
+
+        let remaining = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        let late = env.ledger().timestamp() > loan.due_date;
+        Self::update_stats(env, &loan.borrower, |st| {
+            st.interest_paid += interest_pay;
+            st.outstanding -= principal_pay;
+            if remaining == 0 {
+                if late { st.repaid_late += 1 } else { st.repaid_on_time += 1 }
+            }
+        });
+        if remaining == 0 {
+            loan.status = LoanStatus::Repaid;
+            Self::close_open_loan(env, loan.id);
+        } else {
+            let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+            Self::track_health(env, loan, &config)?;
+        }
+
+        Self::put_loan(env, loan);
+        Ok(remaining)
+    }
+
+    /// Hand an outstanding loan and its still-locked collateral to a new
+    /// borrower on identical terms. Both parties must sign; overdue or
+    /// liquidatable loans can't be assumed, nor any loan while borrowing is paused.
+    /// The seller's collateral recipient is dropped, so the collateral
+    /// returns to the new borrower on repayment.
+    pub fn assume_loan(env: Env, loan_id: u64, new_borrower: Address) -> Result<(), Error> {
+        Self::require_not_paused(&env, PauseOp::Borrow)?;
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+        let old_borrower = loan.borrower.clone();
+        if new_borrower == old_borrower { return Err(Error::NotAuthorized); }
+        old_borrower.require_auth();
+        new_borrower.require_auth();
+
+        let mut projected = loan.clone();
+        Self::project_interest(&env, &mut projected)?;
+        match Self::liquidation_terms(&env, &projected) {
+            Err(Error::NotLiquidatable) => {}
+            Ok(_) => return Err(Error::LoanUnhealthy),
+            Err(e) => return Err(e),
+        }
+
+        let self_addr = env.current_contract_address();
+        for rid in loan.receivable_ids.iter() {
+            Self::call_recv(&env, "force_transfer", soroban_sdk::vec![
+                &env,
+                rid.into_val(&env),
+                new_borrower.clone().into_val(&env),
+                self_addr.clone().into_val(&env),
+            ])?;
+        }
+
+        let old_list: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::BorrowerLoans(old_borrower.clone()))
+            .unwrap_or(Vec::new(&env));
+        let mut new_old = Vec::new(&env);
+        for lid in old_list.iter() {
+            if lid != loan_id { new_old.push_back(lid); }
+        }
+        env.storage().persistent().set(&DataKey::BorrowerLoans(old_borrower.clone()), &new_old);
+
+        let mut new_list: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::BorrowerLoans(new_borrower.clone()))
+            .unwrap_or(Vec::new(&env));
+        new_list.push_back(loan_id);
+        env.storage().persistent().set(&DataKey::BorrowerLoans(new_borrower.clone()), &new_list);
+
+        Self::update_stats(&env, &old_borrower, |st| st.outstanding -= loan.principal);
+        Self::update_stats(&env, &new_borrower, |st| st.outstanding += loan.principal);
+
+        env.storage().persistent().remove(&DataKey::CollateralRecipient(loan_id));
+        loan.borrower = new_borrower.clone();
+        Self::put_loan(&env, &loan);
+
+        env.events().publish((symbol_short!("assume"), loan_id), (old_borrower, new_borrower));
+        Ok(())
+    }
+
+    /// Swap one of the loan's receivables for another the borrower owns,
+    /// without unwinding the loan: `out_id` is unlocked back to the borrower
+    /// and `in_id` locked in its place. `in_id` must pass the same checks as
+    /// at origination, and the post-swap basket must pass the concentration
+    /// caps and back the debt (accrued to now) under its collateral factors,
+    /// as `borrow` would require.
+    pub fn substitute_collateral(env: Env, borrower: Address, loan_id: u64, out_id: u64, in_id: u64) -> Result<i128, Error> {
+        Self::require_not_paused(&env, PauseOp::Borrow)?;
+        borrower.require_auth();
+
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+        if loan.borrower != borrower { return Err(Error::NotBorrower); }
+        let slot = loan.receivable_ids.first_index_of(out_id).ok_or(Error::NotInLoan)?;
+
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::accrue(&env, &mut loan)?;
+        let mut kept = loan.receivable_ids.clone();
+        kept.remove(slot);
+        loan.receivable_ids.set(slot, in_id);
+        let (value, max_borrow, per_debtor) = Self::pledge_value(
+            &env, &config, &borrower, &loan.receivable_ids, config.max_ltv, None, &kept,
+        )?;
+        let debt = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        if debt > max_borrow { return Err(Error::LTVExceeded); }
+        // Tranche headroom never grows through a swap
+        if value > 0 {
+            loan.terms.max_ltv = core::cmp::min(loan.terms.max_ltv, Self::mul_div(max_borrow, 10000, value)?);
+        }
+
+        let self_addr = env.current_contract_address();
+        Self::call_recv(&env, "unlock", soroban_sdk::vec![&env, out_id.into_val(&env), self_addr.clone().into_val(&env)])?;
+        Self::call_recv(&env, "lock", soroban_sdk::vec![&env, in_id.into_val(&env), self_addr.into_val(&env)])?;
+
+        loan.collateral_value = value;
+        loan.collateral_refreshed_at = env.ledger().timestamp();
+        env.storage().persistent().set(&DataKey::LoanDebtors(loan_id), &per_debtor);
+        Self::sync_exposure(&env, &loan);
+        Self::track_health(&env, &mut loan, &config)?;
+        Self::put_loan(&env, &loan);
+
+        env.events().publish((symbol_short!("subst"), loan_id), (out_id, in_id, value));
+        Ok(value)
+    }
+
+    /// Point an active loan at a new off-chain document (e.g. an amended
+    /// facility agreement). Callable by the borrower or the admin.
+    pub fn update_loan_metadata(env: Env, caller: Address, loan_id: u64, metadata_uri: String) -> Result<(), Error> {
+        caller.require_auth();
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        if caller != loan.borrower && caller != admin { return Err(Error::NotAuthorized); }
+
+        loan.metadata_uri = Some(metadata_uri.clone());
+        Self::put_loan(&env, &loan);
+
+        env.events().publish((symbol_short!("loan_meta"), loan_id), metadata_uri);
+        Ok(())
+    }
+
+    // ========================================================================
+    // Liquidation
+    // ========================================================================
+
+    /// `min_collateral_value` and `max_payment` guard against the loan moving
+    /// between simulation and execution: the call fails with `SlippageExceeded`
+    /// if the seized receivables are worth less than the floor or the price
+    /// exceeds the cap. `preview_liquidation` gives the reference numbers.
+    pub fn liquidate(
+        env: Env,
+        liquidator: Address,
+        loan_id: u64,
+        min_collateral_value: Option<i128>,
+        max_payment: Option<i128>,
+    ) -> Result<(), Error> {
+        Self::require_not_paused(&env, PauseOp::Liquidate)?;
+        liquidator.require_auth();
+
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+
+        Self::accrue(&env, &mut loan)?;
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::revalue_if_stale(&env, &mut loan, &config)?;
+
+        let (total_debt, penalty, _, recovered, shortfall) = Self::liquidation_terms(&env, &loan)?;
+        let (seized, _, seized_value) = Self::select_seizure(&env, &loan, &config, total_debt + penalty)?;
+        if min_collateral_value.is_some_and(|min| seized_value < min)
+            || max_payment.is_some_and(|max| recovered > max)
+        {
+            return Err(Error::SlippageExceeded);
+        }
+
+        // Transfer receivables to liquidator, who pays the recovery price into the vault
+        Self::seize(&env, &mut loan, &seized, &liquidator, recovered)?;
 
         env.events().publish((symbol_short!("liq"), liquidator), (loan_id, recovered, shortfall));
         Ok(())
     }
 
+    /// Admin seizure on off-chain evidence (e.g. fraudulent invoices), ahead
+    /// of anything the LTV or due date would allow. Any open loan qualifies,
+    /// overdue ones included (they stay `Active`). Every receivable goes to
+    /// the configured recovery address, which co-signs and pays the vault the
+    /// undiscounted liquidation price, `min(collateral, debt + penalty)`, so
+    /// LPs aren't left with a write-off while the collateral is worked out.
+    pub fn force_liquidate(env: Env, loan_id: u64, reason: u32) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let recovery: Address = env.storage().instance()
+            .get(&DataKey::RecoveryAddress)
+            .ok_or(Error::NoRecoveryAddress)?;
+        recovery.require_auth();
+
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+        Self::accrue(&env, &mut loan)?;
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::revalue_if_stale(&env, &mut loan, &config)?;
+        let total_debt = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        let penalty = Self::mul_div(total_debt, loan.terms.liquidation_penalty, 10000)?;
+        let recovered = core::cmp::min(loan.collateral_value, total_debt.checked_add(penalty).ok_or(Error::Overflow)?);
+
+        let seized = loan.receivable_ids.clone();
+        Self::seize(&env, &mut loan, &seized, &recovery, recovered)?;
+
+        env.events().publish((symbol_short!("f_liq"), loan_id), (reason, recovery, total_debt, recovered));
+        Ok(())
+    }
+
+    pub fn set_recovery_address(env: Env, recovery: Address) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::RecoveryAddress, &recovery);
+        Ok(())
+    }
+
+    pub fn get_recovery_address(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::RecoveryAddress)
+    }
+
+    /// Dry-run `liquidate` at the current ledger time without writing storage.
+    /// Fails with `NotLiquidatable` exactly when `liquidate` would.
+    pub fn preview_liquidation(env: Env, loan_id: u64) -> Result<LiquidationPreview, Error> {
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+
+        Self::project_interest(&env, &mut loan)?;
+
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::revalue_if_stale(&env, &mut loan, &config)?;
+        let (total_debt, penalty, escalation, recovered, shortfall) = Self::liquidation_terms(&env, &loan)?;
+        let liquidatable_since = Self::liquidation_start(&env, &loan)?.unwrap_or(0);
+
+        // Per-receivable values use the live config and pick what is seized;
+        // recovery is bounded by the loan's stored collateral value.
+        let (seized, receivable_values, seized_value) = Self::select_seizure(&env, &loan, &config, total_debt + penalty)?;
+
+        Ok(LiquidationPreview {
+            loan_id,
+            total_debt,
+            penalty,
+            receivable_ids: loan.receivable_ids,
+            receivable_values,
+            seized,
+            seized_value,
+            collateral_value: loan.collateral_value,
+            liquidatable_since,
+            escalation,
+            recovered,
+            shortfall,
+        })
+    }
+
+    /// Re-price a loan's collateral under the live config and risk oracle
+    /// (defaulted receivables count as worthless) and stamp
+    /// `collateral_refreshed_at`. Permissionless so keepers can surface
+    /// deteriorating collateral.
+    pub fn refresh_collateral(env: Env, loan_id: u64) -> Result<i128, Error> {
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
+
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        let old_value = loan.collateral_value;
+        let (value, per_debtor) = Self::collateral_by_debtor(&env, &loan, &config)?;
+        loan.collateral_value = value;
+        loan.collateral_refreshed_at = env.ledger().timestamp();
+        env.storage().persistent().set(&DataKey::LoanDebtors(loan_id), &per_debtor);
+        Self::track_health(&env, &mut loan, &config)?;
+        Self::put_loan(&env, &loan);
+
+        let delta = loan.collateral_value - old_value;
+        env.events().publish((symbol_short!("refresh"), loan_id), (old_value, loan.collateral_value, delta));
+        Ok(loan.collateral_value)
+    }
+
     // ========================================================================
     // Interest
     // ========================================================================
@@ -378,34 +1399,238 @@ impl BorrowContract {
         let mut loan = Self::get_internal(&env, loan_id)?;
         if loan.status != LoanStatus::Active { return Err(Error::InvalidStatus); }
         Self::accrue(&env, &mut loan)?;
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::track_health(&env, &mut loan, &config)?;
         let interest = loan.accrued_interest;
-        env.storage().persistent().set(&DataKey::Loan(loan_id), &loan);
+        Self::put_loan(&env, &loan);
         Ok(interest)
     }
 
+    /// Re-extend a loan's storage TTL (see `bump_loan_ttl`) without touching
+    /// its state. Permissionless so keepers can keep long-dated loans alive.
+    pub fn bump_loan(env: Env, loan_id: u64) -> Result<(), Error> {
+        let loan = Self::get_internal(&env, loan_id)?;
+        Self::bump_loan_ttl(&env, &loan);
+        Ok(())
+    }
+
+    /// Keeper sweep: accrue each live loan in `loan_ids`, refresh its
+    /// liquidatable-since mark and emit a `health` event with
+    /// (ltv, total_owed, seconds_to_due, liquidatable). Unknown and closed
+    /// loans are skipped. Returns the number of loans swept.
+    pub fn sweep_health(env: Env, loan_ids: Vec<u64>) -> Result<u32, Error> {
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        let now = env.ledger().timestamp();
+        let mut swept = 0u32;
+        for loan_id in loan_ids.iter() {
+            let Ok(mut loan) = Self::get_internal(&env, loan_id) else { continue };
+            if loan.status != LoanStatus::Active { continue; }
+
+            Self::accrue(&env, &mut loan)?;
+            Self::track_health(&env, &mut loan, &config)?;
+            Self::put_loan(&env, &loan);
+
+            let total_owed = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+            let ltv = Self::ltv_bps(total_owed, loan.collateral_value)?;
+            env.events().publish(
+                (symbol_short!("health"), loan_id),
+                (ltv, total_owed, loan.due_date.saturating_sub(now), loan.liquidatable_since != 0),
+            );
+            swept += 1;
+        }
+        Ok(swept)
+    }
+
+    /// `project_interest` for a loan about to be written back, publishing
+    /// `("accrued",) -> (loan_id, interest_added, accrued_interest, timestamp)`
+    /// when it adds anything. Views project without the event.
     fn accrue(env: &Env, loan: &mut Loan) -> Result<(), Error> {
+        let before = loan.accrued_interest;
+        Self::project_interest(env, loan)?;
+        let added = loan.accrued_interest - before;
+        if added != 0 {
+            env.events().publish(
+                (symbol_short!("accrued"),),
+                (loan.id, added, loan.accrued_interest, env.ledger().timestamp()),
+            );
+        }
+        Ok(())
+    }
+
+    /// `project_interest` for read paths when `accrue_on_read` is set, so
+    /// views agree with what `accrue_interest` would store
+    fn project_on_read(env: &Env, loan: &mut Loan, config: &BorrowConfig) -> Result<(), Error> {
+        if config.accrue_on_read && loan.status == LoanStatus::Active {
+            Self::project_interest(env, loan)?;
+        }
+        Ok(())
+    }
+
+    /// Bring `accrued_interest` up to now in memory
+    fn project_interest(env: &Env, loan: &mut Loan) -> Result<(), Error> {
         let now = env.ledger().timestamp();
         let elapsed = now.saturating_sub(loan.last_interest_update);
         if elapsed == 0 { return Ok(()); }
 
-        // Simple interest: principal * rate_bps * elapsed / (YEAR * 10000)
-        let num = (loan.principal as u128)
-            .checked_mul(loan.interest_rate as u128).ok_or(Error::Overflow)?
-            .checked_mul(elapsed as u128).ok_or(Error::Overflow)?;
-        let den = (SECONDS_PER_YEAR as u128) * 10000u128;
-        let new_interest = (num / den) as i128;
+        let terms = &loan.terms;
+        // A zero-coupon loan's interest was booked at origination
+        let rate = if loan.fixed_repayment > 0 { 0 } else { loan.interest_rate };
+        let new_interest = if terms.accrual_after_due == AccrualAfterDue::Continue {
+            Self::simple_interest(loan.principal, rate, elapsed)?
+        } else {
+            // Split the window at maturity; only the late part changes rate
+            let cutoff = core::cmp::max(loan.last_interest_update, core::cmp::min(now, loan.due_date));
+            let before_due = cutoff - loan.last_interest_update;
+            let after_due = now - cutoff;
+            let late_rate = match terms.accrual_after_due {
+                AccrualAfterDue::PenaltyOnly => terms.late_penalty_rate,
+                _ => 0,
+            };
+            Self::simple_interest(loan.principal, rate, before_due)?
+                .checked_add(Self::simple_interest(loan.principal, late_rate, after_due)?)
+                .ok_or(Error::Overflow)?
+        };
 
-        loan.accrued_interest = loan.accrued_interest.checked_add(new_interest).ok_or(Error::Overflow)?;
+        // Late fee lands on the one accrual whose window crosses maturity
+        let late_fee = if loan.last_interest_update <= loan.due_date && now > loan.due_date {
+            Self::mul_div(loan.principal, terms.late_fee_bps, 10000)?
+        } else {
+            0
+        };
+
+        loan.accrued_interest = loan.accrued_interest
+            .checked_add(new_interest).ok_or(Error::Overflow)?
+            .checked_add(late_fee).ok_or(Error::Overflow)?;
         loan.last_interest_update = now;
         Ok(())
     }
 
+    /// Simple interest: principal * rate_bps * elapsed / (YEAR * 10000)
+    fn simple_interest(principal: i128, rate: i128, elapsed: u64) -> Result<i128, Error> {
+        let num = (principal as u128)
+            .checked_mul(rate as u128).ok_or(Error::Overflow)?
+            .checked_mul(elapsed as u128).ok_or(Error::Overflow)?;
+        let den = (SECONDS_PER_YEAR as u128) * 10000u128;
+        Ok((num / den) as i128)
+    }
+
     // ========================================================================
     // View
     // ========================================================================
 
     pub fn get_loan(env: Env, loan_id: u64) -> Result<Loan, Error> {
-        Self::get_internal(&env, loan_id)
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::project_on_read(&env, &mut loan, &config)?;
+        Ok(loan)
+    }
+
+    /// Who receives the collateral when the loan is repaid in full (`None`:
+    /// it stays with the borrower)
+    pub fn collateral_recipient(env: Env, loan_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::CollateralRecipient(loan_id))
+    }
+
+    /// What it would take to close the loan now: principal plus interest
+    /// projected to the current time, plus any prepayment fee. Fixed for a
+    /// zero-coupon loan until maturity.
+    pub fn payoff_amount(env: Env, loan_id: u64) -> Result<i128, Error> {
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        if loan.status != LoanStatus::Active { return Ok(0); }
+        Self::project_interest(&env, &mut loan)?;
+        loan.principal
+            .checked_add(loan.accrued_interest).ok_or(Error::Overflow)?
+            .checked_add(Self::prepayment_fee(&env, &loan)?).ok_or(Error::Overflow)
+    }
+
+    /// Loan snapshot with interest projected to now, without writing storage.
+    /// `refresh_collateral` re-prices the receivables under the live config
+    /// (one cross-contract read each) instead of using the origination value.
+    pub fn get_loan_live(env: Env, loan_id: u64, refresh_collateral: bool) -> Result<LoanView, Error> {
+        let loan = Self::get_internal(&env, loan_id)?;
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        let now = env.ledger().timestamp();
+
+        let mut projected = loan.clone();
+        if projected.status == LoanStatus::Active {
+            Self::project_interest(&env, &mut projected)?;
+            if !refresh_collateral { Self::revalue_if_stale(&env, &mut projected, &config)?; }
+        }
+
+        let collateral_value = if refresh_collateral {
+            Self::current_collateral_value(&env, &loan, &config)?
+        } else {
+            projected.collateral_value
+        };
+
+        let total_owed = projected.principal.checked_add(projected.accrued_interest).ok_or(Error::Overflow)?;
+        let current_ltv = Self::ltv_bps(total_owed, collateral_value)?;
+        let health_factor = if total_owed == 0 {
+            i128::MAX
+        } else {
+            Self::mul_div(collateral_value, loan.terms.liquidation_threshold, total_owed)?
+        };
+        let is_liquidatable = loan.status == LoanStatus::Active
+            && (now > loan.due_date || current_ltv > loan.terms.liquidation_threshold);
+
+        let mut loan = loan;
+        Self::project_on_read(&env, &mut loan, &config)?;
+        Ok(LoanView {
+            projected_interest: projected.accrued_interest,
+            total_owed,
+            prepayment_fee: if projected.status == LoanStatus::Active { Self::prepayment_fee(&env, &projected)? } else { 0 },
+            current_ltv,
+            health_factor,
+            is_liquidatable,
+            seconds_to_due: loan.due_date.saturating_sub(now),
+            loan,
+        })
+    }
+
+    /// The loan's collateral receivables in pledge order (one batched read)
+    pub fn get_loan_receivables(env: Env, loan_id: u64) -> Result<Vec<Receivable>, Error> {
+        let loan = Self::get_internal(&env, loan_id)?;
+        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
+        Self::fetch_recvs(&env, &recv_addr, &loan.receivable_ids)
+    }
+
+    /// `get_loan_live` (origination collateral value) plus the collateral receivables
+    pub fn get_loan_detail(env: Env, loan_id: u64) -> Result<LoanDetail, Error> {
+        let view = Self::get_loan_live(env.clone(), loan_id, false)?;
+        let receivables = Self::get_loan_receivables(env, loan_id)?;
+        Ok(LoanDetail { view, receivables })
+    }
+
+    /// Page of active loans past due, whether or not anyone has liquidated
+    /// them yet. Ordered by origination rather than days overdue: that is the
+    /// exact order when loans share a term and only approximate otherwise.
+    pub fn get_overdue_loans(env: Env, start: u32, limit: u32) -> Result<Vec<LoanView>, Error> {
+        let mut out = Vec::new(&env);
+        for loan in Self::overdue_loans(&env).iter().skip(start as usize).take(limit as usize) {
+            out.push_back(Self::get_loan_live(env.clone(), loan.id, false)?);
+        }
+        Ok(out)
+    }
+
+    /// Outstanding principal across every overdue loan
+    pub fn overdue_principal(env: Env) -> Result<i128, Error> {
+        let mut total: i128 = 0;
+        for loan in Self::overdue_loans(&env).iter() {
+            total = total.checked_add(loan.principal).ok_or(Error::Overflow)?;
+        }
+        Ok(total)
+    }
+
+    /// Outstanding principal across all active loans attributed to
+    /// `debtor_hash`: each loan's principal split pro rata by the collateral
+    /// value each debtor backs (weights set at origination and by
+    /// `refresh_collateral`).
+    pub fn exposure_of(env: Env, debtor_hash: BytesN<32>) -> i128 {
+        env.storage().persistent().get(&DataKey::DebtorExposure(debtor_hash)).unwrap_or(0)
+    }
+
+    pub fn get_borrower_stats(env: Env, borrower: Address) -> BorrowerStats {
+        env.storage().persistent().get(&DataKey::BorrowerStats(borrower)).unwrap_or_default()
     }
 
     pub fn get_borrower_loans(env: Env, borrower: Address) -> Vec<u64> {
@@ -415,26 +1640,38 @@ impl BorrowContract {
     }
 
     pub fn get_ltv(env: Env, loan_id: u64) -> Result<i128, Error> {
-        let loan = Self::get_internal(&env, loan_id)?;
-        let now = env.ledger().timestamp();
-        let elapsed = now.saturating_sub(loan.last_interest_update);
-        let mut interest = loan.accrued_interest;
-        if elapsed > 0 {
-            let num = (loan.principal as u128) * (loan.interest_rate as u128) * (elapsed as u128);
-            let den = (SECONDS_PER_YEAR as u128) * 10000u128;
-            interest += (num / den) as i128;
-        }
-        let total = loan.principal + interest;
-        Self::mul_div(total, 10000, loan.collateral_value)
+        // Project pending interest on a copy, with the same checked math as accrual
+        let mut loan = Self::get_internal(&env, loan_id)?;
+        Self::project_interest(&env, &mut loan)?;
+        let total = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        Self::ltv_bps(total, loan.collateral_value)
     }
 
     pub fn is_liquidatable(env: Env, loan_id: u64) -> Result<bool, Error> {
-        let loan = Self::get_internal(&env, loan_id)?;
+        let mut loan = Self::get_internal(&env, loan_id)?;
         if loan.status != LoanStatus::Active { return Ok(false); }
-        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
         if env.ledger().timestamp() > loan.due_date { return Ok(true); }
-        let ltv = Self::get_ltv(env, loan_id)?;
-        Ok(ltv > config.liquidation_threshold)
+        Self::project_interest(&env, &mut loan)?;
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::revalue_if_stale(&env, &mut loan, &config)?;
+        let total = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        Ok(Self::ltv_bps(total, loan.collateral_value)? > loan.terms.liquidation_threshold)
+    }
+
+    /// Keeper scan: the ids in `start_id..start_id + limit` that `is_liquidatable`
+    /// right now, with live accrual. `limit` is capped at `MAX_LIQUIDATION_SCAN`
+    /// and counts ids checked, not ids returned, so page on with
+    /// `start_id + limit` until past `total_loans`.
+    pub fn liquidatable_loans(env: Env, start_id: u64, limit: u32) -> Vec<u64> {
+        let next_id: u64 = env.storage().instance().get(&DataKey::NextLoanId).unwrap();
+        let end = start_id.saturating_add(limit.min(MAX_LIQUIDATION_SCAN) as u64).min(next_id);
+        let mut out = Vec::new(&env);
+        for loan_id in start_id.max(1)..end {
+            if let Ok(true) = Self::is_liquidatable(env.clone(), loan_id) {
+                out.push_back(loan_id);
+            }
+        }
+        out
     }
 
     pub fn get_config(env: Env) -> BorrowConfig {
@@ -449,36 +1686,2831 @@ impl BorrowContract {
     // Admin
     // ========================================================================
 
+    /// Stop new credit only — repayments and liquidations stay open
     pub fn pause(env: Env) -> Result<(), Error> {
+        Self::set_pause_flags(env, PauseFlags { borrow: true, repay: false, liquidate: false })
+    }
+
+    pub fn unpause(env: Env) -> Result<(), Error> {
+        Self::set_pause_flags(env, PauseFlags::default())
+    }
+
+    pub fn set_pause_flags(env: Env, flags: PauseFlags) -> Result<(), Error> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
-        env.storage().instance().set(&DataKey::Paused, &true);
+        env.storage().instance().set(&DataKey::PauseFlags, &flags);
+        env.storage().instance().remove(&DataKey::Paused);
+        env.events().publish((symbol_short!("pause"),), (flags.borrow, flags.repay, flags.liquidate));
         Ok(())
     }
 
-    pub fn unpause(env: Env) -> Result<(), Error> {
+    /// Override `max_ltv` for receivables in `currency`; `None` reverts to the default
+    pub fn set_collateral_factor(env: Env, currency: Address, factor: Option<i128>) -> Result<(), Error> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
-        env.storage().instance().set(&DataKey::Paused, &false);
+        let key = DataKey::CollateralFactor(currency);
+        match factor {
+            Some(f) if !(0..=10000).contains(&f) => return Err(Error::InvalidConfig),
+            Some(f) => env.storage().instance().set(&key, &f),
+            None => env.storage().instance().remove(&key),
+        }
         Ok(())
     }
 
+    /// Effective LTV cap (bps) for receivables in `currency`
+    pub fn get_collateral_factor(env: Env, currency: Address) -> i128 {
+        let config: BorrowConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        env.storage().instance().get(&DataKey::CollateralFactor(currency)).unwrap_or(config.max_ltv)
+    }
+
+    /// Falls back to the legacy single `Paused` flag, which paused borrowing,
+    /// until the first `set_pause_flags` migrates it
+    pub fn get_pause_flags(env: Env) -> PauseFlags {
+        env.storage().instance().get(&DataKey::PauseFlags).unwrap_or_else(|| PauseFlags {
+            borrow: env.storage().instance().get(&DataKey::Paused).unwrap_or(false),
+            ..PauseFlags::default()
+        })
+    }
+
     // ========================================================================
     // Internal
     // ========================================================================
 
     fn get_internal(env: &Env, id: u64) -> Result<Loan, Error> {
-        env.storage().persistent().get(&DataKey::Loan(id)).ok_or(Error::LoanNotFound)
+        if let Some(loan) = env.storage().persistent().get(&DataKey::LoanV2(id)) {
+            return Ok(loan);
+        }
+        env.storage().persistent()
+            .get::<_, LegacyLoan>(&DataKey::Loan(id))
+            .map(|l| l.upgrade(env))
+            .ok_or(Error::LoanNotFound)
     }
 
-    fn require_not_paused(env: &Env) -> Result<(), Error> {
-        let p: bool = env.storage().instance().get(&DataKey::Paused).unwrap_or(false);
-        if p { Err(Error::ContractPaused) } else { Ok(()) }
+    fn put_loan(env: &Env, loan: &Loan) {
+        env.storage().persistent().set(&DataKey::LoanV2(loan.id), loan);
+        if env.storage().persistent().has(&DataKey::Loan(loan.id)) {
+            env.storage().persistent().remove(&DataKey::Loan(loan.id));
+        }
+        Self::sync_exposure(env, loan);
+        Self::bump_loan_ttl(env, loan);
     }
 
-    fn mul_div(a: i128, b: i128, c: i128) -> Result<i128, Error> {
-        if c == 0 { return Err(Error::Overflow); }
-        Ok(((a as u128).checked_mul(b as u128).ok_or(Error::Overflow)?
-            .checked_div(c as u128).ok_or(Error::Overflow)?) as i128)
-    }
-}
\ No newline at end of file
+    /// Keep the loan, its borrower's records, the open-loan index and the
+    /// instance (config) alive until `LOAN_TTL_MARGIN_LEDGERS` past `due_date`,
+    /// capped at the network's max TTL
+    fn bump_loan_ttl(env: &Env, loan: &Loan) {
+        let remaining = loan.due_date.saturating_sub(env.ledger().timestamp()) / SECS_PER_LEDGER;
+        let target = core::cmp::min(
+            (remaining as u32).saturating_add(LOAN_TTL_MARGIN_LEDGERS),
+            env.storage().max_ttl(),
+        );
+        let storage = env.storage().persistent();
+        for key in [
+            DataKey::LoanV2(loan.id),
+            DataKey::Tranches(loan.id),
+            DataKey::BorrowerLoans(loan.borrower.clone()),
+            DataKey::BorrowerStats(loan.borrower.clone()),
+            DataKey::OpenLoans,
+            DataKey::LoanDebtors(loan.id),
+            DataKey::LoanExposure(loan.id),
+            DataKey::CollateralRecipient(loan.id),
+        ] {
+            if storage.has(&key) { storage.extend_ttl(&key, target, target); }
+        }
+        if let Some(weights) = storage.get::<_, Map<BytesN<32>, i128>>(&DataKey::LoanDebtors(loan.id)) {
+            for debtor in weights.keys().iter() {
+                let key = DataKey::DebtorExposure(debtor);
+                if storage.has(&key) { storage.extend_ttl(&key, target, target); }
+            }
+        }
+        env.storage().instance().extend_ttl(target, target);
+    }
+
+    /// Hand the `seized` receivables to `to` while still locked (no borrower
+    /// signature needed; the rest stay with the borrower), unlock the loan's
+    /// collateral, settle `recovered` (paid by `to`) against the principal in
+    /// the vault and close the loan as liquidated.
+    fn seize(env: &Env, loan: &mut Loan, seized: &Vec<u64>, to: &Address, recovered: i128) -> Result<(), Error> {
+        let self_addr = env.current_contract_address();
+        for rid in loan.receivable_ids.iter() {
+            if seized.contains(rid) {
+                Self::call_recv(env, "force_transfer", soroban_sdk::vec![
+                    env,
+                    rid.into_val(env),
+                    to.into_val(env),
+                    self_addr.clone().into_val(env),
+                ])?;
+            }
+            Self::call_recv(env, "unlock", soroban_sdk::vec![env, rid.into_val(env), self_addr.clone().into_val(env)])?;
+        }
+
+        let vault_addr: Address = env.storage().instance().get(&DataKey::VaultContract).unwrap();
+        let _: () = env.invoke_contract(
+            &vault_addr,
+            &Symbol::new(env, "liq_recv"),
+            soroban_sdk::vec![
+                env,
+                to.clone().into_val(env),
+                loan.principal.into_val(env),
+                recovered.into_val(env),
+            ],
+        );
+
+        loan.status = LoanStatus::Liquidated;
+        Self::put_loan(env, loan);
+        Self::close_open_loan(env, loan.id);
+        Self::update_stats(env, &loan.borrower, |st| {
+            st.liquidated += 1;
+            st.outstanding -= loan.principal;
+        });
+        Ok(())
+    }
+
+    fn close_open_loan(env: &Env, loan_id: u64) {
+        let mut open: Vec<u64> = env.storage().persistent().get(&DataKey::OpenLoans).unwrap_or(Vec::new(env));
+        if let Some(i) = open.first_index_of(loan_id) {
+            open.remove(i);
+            env.storage().persistent().set(&DataKey::OpenLoans, &open);
+        }
+    }
+
+    /// Active loans past their due date, in origination order
+    fn overdue_loans(env: &Env) -> Vec<Loan> {
+        let now = env.ledger().timestamp();
+        let open: Vec<u64> = env.storage().persistent().get(&DataKey::OpenLoans).unwrap_or(Vec::new(env));
+        let mut out = Vec::new(env);
+        for loan_id in open.iter() {
+            let Ok(loan) = Self::get_internal(env, loan_id) else { continue };
+            if now > loan.due_date { out.push_back(loan); }
+        }
+        out
+    }
+
+    fn update_stats(env: &Env, borrower: &Address, f: impl FnOnce(&mut BorrowerStats)) {
+        let key = DataKey::BorrowerStats(borrower.clone());
+        let mut stats: BorrowerStats = env.storage().persistent().get(&key).unwrap_or_default();
+        f(&mut stats);
+        env.storage().persistent().set(&key, &stats);
+    }
+
+    /// Liquidation math shared by `liquidate` and `preview_liquidation`.
+    /// Expects `loan` to be accrued to now; returns (total_debt, penalty,
+    /// escalation_bps, recovered, shortfall).
+    /// `recovered` is what the liquidator pays for the collateral: the usual
+    /// `min(collateral, debt + penalty)` less a discount that grows by
+    /// `liquidation_escalation` bps per hour the loan has sat liquidatable.
+    fn liquidation_terms(env: &Env, loan: &Loan) -> Result<(i128, i128, i128, i128, i128), Error> {
+        let terms = &loan.terms;
+        let now = env.ledger().timestamp();
+        let since = Self::liquidation_start(env, loan)?.ok_or(Error::NotLiquidatable)?;
+        let total_debt = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+
+        let hours = ((now - since) / 3600) as i128;
+        let escalation = core::cmp::min(
+            hours.saturating_mul(terms.liquidation_escalation),
+            terms.max_liquidation_escalation,
+        ).clamp(0, 10000);
+
+        let penalty = Self::mul_div(total_debt, terms.liquidation_penalty, 10000)?;
+        let liq_value = total_debt.checked_add(penalty).ok_or(Error::Overflow)?;
+        let price = core::cmp::min(loan.collateral_value, liq_value);
+        let recovered = price - Self::mul_div(price, escalation, 10000)?;
+        let shortfall = core::cmp::max(0, total_debt - recovered);
+        Ok((total_debt, penalty, escalation, recovered, shortfall))
+    }
+
+    /// Receivables a liquidator takes: cheapest first by live discounted value
+    /// (pledge order breaks ties) until they cover `target`, or all of them
+    /// if even the whole basket falls short. Defaulted or disputed ones are
+    /// worth nothing and never taken. Also returns every receivable's value
+    /// in pledge order and the total value seized.
+    fn select_seizure(env: &Env, loan: &Loan, config: &BorrowConfig, target: i128) -> Result<(Vec<u64>, Vec<i128>, i128), Error> {
+        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
+        let mut values = Vec::new(env);
+        let mut taken = Vec::new(env);
+        for (i, recv) in Self::fetch_recvs(env, &recv_addr, &loan.receivable_ids)?.iter().enumerate() {
+            if Self::is_valued(&recv) {
+                values.push_back(Self::discounted_value(env, &recv, config)?);
+            } else {
+                values.push_back(0);
+                taken.push_back(i as u32);
+            }
+        }
+
+        let mut seized = Vec::new(env);
+        let mut covered: i128 = 0;
+        while covered < target && taken.len() < values.len() {
+            let mut pick = None;
+            for (i, value) in values.iter().enumerate() {
+                let i = i as u32;
+                if taken.contains(i) { continue; }
+                if pick.is_none_or(|(_, best)| value < best) { pick = Some((i, value)); }
+            }
+            let (i, value) = pick.unwrap();
+            taken.push_back(i);
+            seized.push_back(loan.receivable_ids.get(i).unwrap());
+            covered = covered.checked_add(value).ok_or(Error::Overflow)?;
+        }
+        Ok((seized, values, covered))
+    }
+
+    /// When the loan became liquidatable, or `None` if it isn't now. Overdue
+    /// loans count from `due_date`; an LTV breach counts from when a keeper
+    /// call first recorded it, or from now if none has.
+    fn liquidation_start(env: &Env, loan: &Loan) -> Result<Option<u64>, Error> {
+        let now = env.ledger().timestamp();
+
+        let total_debt = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        let current_ltv = Self::ltv_bps(total_debt, loan.collateral_value)?;
+
+        let is_underwater = current_ltv > loan.terms.liquidation_threshold;
+        let is_overdue = now > loan.due_date;
+
+        if !is_underwater && !is_overdue { return Ok(None); }
+
+        let mut since = now;
+        if loan.liquidatable_since != 0 { since = core::cmp::min(since, loan.liquidatable_since); }
+        if is_overdue { since = core::cmp::min(since, loan.due_date); }
+        Ok(Some(since))
+    }
+
+    /// Record (or clear) the liquidatable-since timestamp and the LTV
+    /// warning flag on a loan about to be stored. Emits `ltv_warn` the first
+    /// time LTV crosses `warning_threshold`; the flag only clears once LTV is
+    /// `LTV_WARN_HYSTERESIS` bps back below it.
+    fn track_health(env: &Env, loan: &mut Loan, config: &BorrowConfig) -> Result<(), Error> {
+        loan.liquidatable_since = Self::liquidation_start(env, loan)?.unwrap_or(0);
+
+        if config.warning_threshold <= 0 {
+            loan.warned = false;
+            return Ok(());
+        }
+        let total_owed = loan.principal.checked_add(loan.accrued_interest).ok_or(Error::Overflow)?;
+        let ltv = Self::ltv_bps(total_owed, loan.collateral_value)?;
+        if !loan.warned && ltv > config.warning_threshold {
+            loan.warned = true;
+            env.events().publish((symbol_short!("ltv_warn"), loan.id), (ltv, config.warning_threshold));
+        } else if loan.warned && ltv < config.warning_threshold - LTV_WARN_HYSTERESIS {
+            loan.warned = false;
+        }
+        Ok(())
+    }
+
+    fn validate_config(config: &BorrowConfig) -> Result<(), Error> {
+        if config.warning_threshold < 0 || config.warning_threshold >= config.liquidation_threshold {
+            return Err(Error::InvalidConfig);
+        }
+        if config.min_loan_duration > config.max_loan_duration {
+            return Err(Error::InvalidConfig);
+        }
+        if !(0..=10000).contains(&config.late_fee_bps) || !(0..=10000).contains(&config.prepayment_fee_bps) {
+            return Err(Error::InvalidConfig);
+        }
+        if config.min_repay < 0 || !(0..=10000).contains(&config.min_repay_bps) {
+            return Err(Error::InvalidConfig);
+        }
+        if !(0..=10000).contains(&config.matured_haircut_bps) || !(0..=10000).contains(&config.max_single_collateral_bps) {
+            return Err(Error::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    /// Validate a basket of receivables for pledging and sum their discounted value
+    /// and what it can back: each receivable's value times its currency's
+    /// collateral factor (`default_ltv` where none is set). Returns (value,
+    /// max_borrow, value per debtor_hash).
+    /// Receivables over `max_risk_score` fail with `RiskTooHigh`, or are skipped
+    /// and collected into `excluded` when it is given.
+    /// Ids in `locked` are already pledged to the loan being re-priced: they
+    /// skip the eligibility checks but count toward value and the caps, except
+    /// defaulted or disputed ones, which are worth nothing.
+    fn pledge_value(
+        env: &Env,
+        config: &BorrowConfig,
+        borrower: &Address,
+        receivable_ids: &Vec<u64>,
+        default_ltv: i128,
+        mut excluded: Option<&mut Vec<u64>>,
+        locked: &Vec<u64>,
+    ) -> Result<(i128, i128, Map<BytesN<32>, i128>), Error> {
+        if receivable_ids.len() > MAX_LOAN_RECEIVABLES { return Err(Error::TooManyReceivables); }
+
+        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
+        let mut total_collateral: i128 = 0;
+        let mut max_borrow: i128 = 0;
+        let mut per_debtor: Map<BytesN<32>, i128> = Map::new(env);
+        let mut pledged = 0u32;
+        let mut largest: i128 = 0;
+        for recv in Self::fetch_recvs(env, &recv_addr, receivable_ids)?.iter() {
+            let rid = recv.id;
+            let is_locked = locked.contains(rid);
+            if is_locked {
+                if !Self::is_valued(&recv) { continue; }
+            } else {
+                if recv.owner != *borrower { return Err(Error::RecvNotOwned); }
+                let matured_ok = config.allow_matured_collateral && recv.status == ReceivableStatus::Matured;
+                if recv.status != ReceivableStatus::Active && !matured_ok { return Err(Error::RecvNotActive); }
+                if config.require_debtor_ack && !recv.debtor_acknowledged {
+                    return Err(Error::RecvNotAcknowledged);
+                }
+                // Leave time for off-chain checks of the underlying invoice
+                if env.ledger().timestamp().saturating_sub(recv.issuance_date) < config.min_collateral_age_secs {
+                    return Err(Error::CollateralTooNew);
+                }
+            }
+            let risk_score = Self::effective_risk_score(env, &recv, config);
+            if risk_score > config.max_risk_score && !is_locked {
+                match excluded.as_deref_mut() {
+                    Some(list) => { list.push_back(rid); continue; }
+                    None => {
+                        log!(env, "receivable over max_risk_score", rid, risk_score);
+                        return Err(Error::RiskTooHigh);
+                    }
+                }
+            }
+            pledged += 1;
+
+            let disc_val = Self::discounted_value(env, &recv, config)?;
+            total_collateral = total_collateral.checked_add(disc_val).ok_or(Error::Overflow)?;
+            largest = core::cmp::max(largest, disc_val);
+            let factor: i128 = env.storage().instance()
+                .get(&DataKey::CollateralFactor(recv.currency.clone()))
+                .unwrap_or(default_ltv);
+            max_borrow = max_borrow.checked_add(Self::mul_div(disc_val, factor, 10000)?).ok_or(Error::Overflow)?;
+            let debtor_total = per_debtor.get(recv.debtor_hash.clone()).unwrap_or(0);
+            per_debtor.set(recv.debtor_hash, debtor_total.checked_add(disc_val).ok_or(Error::Overflow)?);
+        }
+
+        // Single-receivable baskets can't be diversified, so the cap only
+        // applies once there is more than one receivable
+        if config.max_single_debtor_bps > 0 && pledged > 1 && total_collateral > 0 {
+            let cap = Self::mul_div(total_collateral, config.max_single_debtor_bps, 10000)?;
+            for (_, value) in per_debtor.iter() {
+                if value > cap { return Err(Error::DebtorConcentration); }
+            }
+        }
+        if config.max_single_collateral_bps > 0 && pledged > 1 && total_collateral > 0
+            && largest > Self::mul_div(total_collateral, config.max_single_collateral_bps, 10000)?
+        {
+            return Err(Error::CollateralConcentration);
+        }
+        Ok((total_collateral, max_borrow, per_debtor))
+    }
+
+    /// Sum of the loan's receivables valued under the given config
+    fn current_collateral_value(env: &Env, loan: &Loan, config: &BorrowConfig) -> Result<i128, Error> {
+        Ok(Self::collateral_by_debtor(env, loan, config)?.0)
+    }
+
+    /// `current_collateral_value` plus its split by debtor_hash
+    fn collateral_by_debtor(env: &Env, loan: &Loan, config: &BorrowConfig) -> Result<(i128, Map<BytesN<32>, i128>), Error> {
+        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
+        let mut total: i128 = 0;
+        let mut per_debtor: Map<BytesN<32>, i128> = Map::new(env);
+        for recv in Self::fetch_recvs(env, &recv_addr, &loan.receivable_ids)?.iter() {
+            if !Self::is_valued(&recv) { continue; }
+            let value = Self::discounted_value(env, &recv, config)?;
+            total = total.checked_add(value).ok_or(Error::Overflow)?;
+            let debtor_total = per_debtor.get(recv.debtor_hash.clone()).unwrap_or(0);
+            per_debtor.set(recv.debtor_hash, debtor_total.checked_add(value).ok_or(Error::Overflow)?);
+        }
+        Ok((total, per_debtor))
+    }
+
+    /// Move this loan's share of book-wide debtor exposure to its current
+    /// state: outstanding principal split across debtors in proportion to the
+    /// collateral value each backs (rounded down), or nothing once closed.
+    fn sync_exposure(env: &Env, loan: &Loan) {
+        let storage = env.storage().persistent();
+        let Some(weights) = storage.get::<_, Map<BytesN<32>, i128>>(&DataKey::LoanDebtors(loan.id)) else { return };
+        let old: Map<BytesN<32>, i128> = storage.get(&DataKey::LoanExposure(loan.id)).unwrap_or(Map::new(env));
+
+        let mut new: Map<BytesN<32>, i128> = Map::new(env);
+        let total_weight: i128 = weights.values().iter().sum();
+        if loan.status == LoanStatus::Active && total_weight > 0 {
+            for (debtor, weight) in weights.iter() {
+                new.set(debtor, Self::mul_div(loan.principal, weight, total_weight).unwrap_or(0));
+            }
+        }
+
+        let mut deltas = new.clone();
+        for (debtor, applied) in old.iter() {
+            deltas.set(debtor.clone(), deltas.get(debtor).unwrap_or(0) - applied);
+        }
+        for (debtor, delta) in deltas.iter() {
+            if delta == 0 { continue; }
+            let key = DataKey::DebtorExposure(debtor);
+            let exposure: i128 = storage.get(&key).unwrap_or(0);
+            storage.set(&key, &(exposure + delta));
+        }
+        storage.set(&DataKey::LoanExposure(loan.id), &new);
+    }
+
+    /// Re-price in place when the stored valuation is older than
+    /// `max_staleness_secs`, so liquidation checks never act on a stale one
+    fn revalue_if_stale(env: &Env, loan: &mut Loan, config: &BorrowConfig) -> Result<(), Error> {
+        let now = env.ledger().timestamp();
+        if config.max_staleness_secs == 0 || now - loan.collateral_refreshed_at <= config.max_staleness_secs {
+            return Ok(());
+        }
+        loan.collateral_value = Self::current_collateral_value(env, loan, config)?;
+        loan.collateral_refreshed_at = now;
+        Ok(())
+    }
+
+    /// A basket's receivables in one `get_recvs` call, in the same order.
+    /// Baskets stay within the receivable token's batch cap
+    /// (`MAX_LOAN_RECEIVABLES` < `MAX_BATCH_READ`). A missing id is `RecvError`.
+    fn fetch_recvs(env: &Env, recv_addr: &Address, receivable_ids: &Vec<u64>) -> Result<Vec<Receivable>, Error> {
+        let found: Vec<Option<Receivable>> = env.invoke_contract(
+            recv_addr,
+            &Symbol::new(env, "get_recvs"),
+            soroban_sdk::vec![env, receivable_ids.into_val(env)],
+        );
+        let mut out = Vec::new(env);
+        for recv in found.iter() {
+            out.push_back(recv.ok_or(Error::RecvError)?);
+        }
+        Ok(out)
+    }
+
+    /// Whether a pledged receivable counts toward collateral: defaulted and
+    /// disputed ones are worth zero
+    fn is_valued(recv: &Receivable) -> bool {
+        !matches!(recv.status, ReceivableStatus::Defaulted | ReceivableStatus::Disputed)
+    }
+
+    /// Risk-discounted collateral value of a single receivable
+    fn discounted_value(env: &Env, recv: &Receivable, config: &BorrowConfig) -> Result<i128, Error> {
+        let risk_score = Self::effective_risk_score(env, recv, config);
+        // A discount above 100% would make `eff` negative and wrap in mul_div's
+        // u128 cast; a fully discounted receivable is simply worth nothing.
+        let risk_disc = Self::mul_div(risk_score as i128, config.risk_discount_factor, 10000)?;
+        let eff = 10000i128.saturating_sub(core::cmp::min(risk_disc, 10000));
+        let value = Self::mul_div(recv.face_value, eff, 10000)?;
+        // Keyed on the date rather than the status, so the haircut survives
+        // the receivable being locked as Collateralized
+        if env.ledger().timestamp() >= recv.maturity_date {
+            return Self::mul_div(value, 10000 - config.matured_haircut_bps, 10000);
+        }
+        Ok(value)
+    }
+
+    /// Debt over collateral in bps; worthless collateral is infinitely leveraged
+    fn ltv_bps(debt: i128, collateral_value: i128) -> Result<i128, Error> {
+        if collateral_value == 0 { return Ok(i128::MAX); }
+        Self::mul_div(debt, 10000, collateral_value)
+    }
+
+    /// The worse of the minted score and the oracle's current score. Any oracle
+    /// failure (unset key, trap, bad return) falls back to the minted score.
+    fn effective_risk_score(env: &Env, recv: &Receivable, config: &BorrowConfig) -> u32 {
+        let oracle = match &config.risk_oracle {
+            Some(o) => o,
+            None => return recv.risk_score,
+        };
+        let res = env.try_invoke_contract::<u32, soroban_sdk::Error>(
+            oracle,
+            &Symbol::new(env, "get_score"),
+            soroban_sdk::vec![env, recv.debtor_hash.clone().into_val(env)],
+        );
+        match res {
+            Ok(Ok(score)) => core::cmp::max(recv.risk_score, score),
+            _ => recv.risk_score,
+        }
+    }
+
+    /// Call the vault, translating its contract errors into ours so borrowers
+    /// see why it refused instead of an opaque cross-contract trap.
+    /// Anything unrecognised (including host failures) becomes `fallback`.
+    fn call_vault(env: &Env, func: &str, args: Vec<Val>, fallback: Error) -> Result<(), Error> {
+        let vault_addr: Address = env.storage().instance().get(&DataKey::VaultContract).unwrap();
+        match env.try_invoke_contract::<(), soroban_sdk::Error>(&vault_addr, &Symbol::new(env, func), args) {
+            Ok(Ok(())) => Ok(()),
+            Err(Ok(e)) => Err(Self::map_vault_error(e, fallback)),
+            _ => Err(fallback),
+        }
+    }
+
+    /// lending_vault::Error codes we surface distinctly
+    fn map_vault_error(e: soroban_sdk::Error, fallback: Error) -> Error {
+        if e == soroban_sdk::Error::from_contract_error(5) {
+            Error::VaultLiquidity
+        } else if e == soroban_sdk::Error::from_contract_error(6) {
+            Error::VaultUtilization
+        } else if e == soroban_sdk::Error::from_contract_error(7) {
+            Error::VaultPaused
+        } else {
+            fallback
+        }
+    }
+
+    fn call_recv(env: &Env, func: &str, args: Vec<Val>) -> Result<(), Error> {
+        let recv_addr: Address = env.storage().instance().get(&DataKey::RecvContract).unwrap();
+        match env.try_invoke_contract::<(), soroban_sdk::Error>(&recv_addr, &Symbol::new(env, func), args) {
+            Ok(Ok(())) => Ok(()),
+            _ => Err(Error::RecvError),
+        }
+    }
+
+    fn require_not_paused(env: &Env, op: PauseOp) -> Result<(), Error> {
+        let flags = Self::get_pause_flags(env.clone());
+        let paused = match op {
+            PauseOp::Borrow => flags.borrow,
+            PauseOp::Repay => flags.repay,
+            PauseOp::Liquidate => flags.liquidate,
+        };
+        if paused { Err(Error::ContractPaused) } else { Ok(()) }
+    }
+
+    fn mul_div(a: i128, b: i128, c: i128) -> Result<i128, Error> {
+        if c == 0 { return Err(Error::Overflow); }
+        Ok(((a as u128).checked_mul(b as u128).ok_or(Error::Overflow)?
+            .checked_div(c as u128).ok_or(Error::Overflow)?) as i128)
+    }
+}
+// ============================================================================
+// Tests
+// ============================================================================
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use super::*;
+    use soroban_sdk::{
+        testutils::{storage::Persistent as _, Address as _, Events, Ledger, LedgerInfo, AuthorizedFunction, MockAuth, MockAuthInvoke},
+        BytesN, Env, FromVal, String, Symbol,
+    };
+    use soroban_sdk::token::{StellarAssetClient, TokenClient};
+    use lending_vault::{FeeToken, LendingVaultContract, LendingVaultContractClient, RATE_SCALE};
+    use receivable_token::{ReceivableTokenContract, ReceivableTokenContractClient};
+
+    #[contract]
+    pub struct MockRiskOracle;
+
+    #[contractimpl]
+    impl MockRiskOracle {
+        pub fn set_score(env: Env, debtor_hash: BytesN<32>, score: u32) {
+            env.storage().instance().set(&debtor_hash, &score);
+        }
+
+        /// Traps for unknown debtors, like a strict production oracle would
+        pub fn get_score(env: Env, debtor_hash: BytesN<32>) -> u32 {
+            env.storage().instance().get(&debtor_hash).unwrap()
+        }
+    }
+
+    #[allow(dead_code)]
+    struct TestContext<'a> {
+        env: Env,
+        client: BorrowContractClient<'a>,
+        recv: ReceivableTokenContractClient<'a>,
+        vault: LendingVaultContractClient<'a>,
+        token: TokenClient<'a>,
+        token_admin: StellarAssetClient<'a>,
+        admin: Address,
+        verifier: Address,
+        borrower: Address,
+        lp: Address,
+        liquidator: Address,
+    }
+
+    fn default_config() -> BorrowConfig {
+        BorrowConfig {
+            max_ltv: 7000,
+            liquidation_threshold: 8500,
+            liquidation_penalty: 500,
+            base_interest_rate: 1200,
+            max_loan_duration: 7_776_000,
+            min_loan_duration: 0,
+            risk_discount_factor: 5000,
+            require_debtor_ack: false,
+            risk_oracle: None,
+            liquidation_escalation: 10,
+            max_liquidation_escalation: 1000,
+            max_single_debtor_bps: 0,
+            accrual_after_due: AccrualAfterDue::Continue,
+            late_penalty_rate: 2400,
+            warning_threshold: 0,
+            late_fee_bps: 0,
+            min_collateral_age_secs: 0,
+            max_staleness_secs: 0,
+            max_interest_only_secs: 0,
+            max_risk_score: 10_000,
+            min_repay: 0,
+            min_repay_bps: 0,
+            allow_matured_collateral: false,
+            matured_haircut_bps: 0,
+            max_single_collateral_bps: 0,
+            prepayment_fee_bps: 0,
+            prepayment_free_window_secs: 0,
+            accrue_on_read: false,
+        }
+    }
+
+    fn setup<'a>() -> TestContext<'a> {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().set(LedgerInfo {
+            timestamp: 1_000_000,
+            protocol_version: 22,
+            sequence_number: 100,
+            network_id: Default::default(),
+            base_reserve: 10,
+            min_temp_entry_ttl: 10,
+            min_persistent_entry_ttl: 100_000,
+            max_entry_ttl: 3_110_400,
+        });
+
+        let admin = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        let borrower = Address::generate(&env);
+        let lp = Address::generate(&env);
+        let liquidator = Address::generate(&env);
+
+        let token_admin_addr = Address::generate(&env);
+        let token_id = env.register_stellar_asset_contract_v2(token_admin_addr.clone());
+        let token = TokenClient::new(&env, &token_id.address());
+        let token_admin = StellarAssetClient::new(&env, &token_id.address());
+
+        let (recv, vault, client) = wire_all(&env, &admin, &verifier, &token_id.address(), None);
+
+        // Seed the vault and give the borrower something to pay interest with
+        token_admin.mint(&lp, &20_000_000);
+        token_admin.mint(&borrower, &1_000_000);
+        token_admin.mint(&liquidator, &5_000_000);
+        vault.deposit(&lp, &10_000_000);
+
+        // Transmute for static lifetime
+        let client = unsafe { core::mem::transmute::<BorrowContractClient<'_>, BorrowContractClient<'a>>(client) };
+        let recv = unsafe { core::mem::transmute::<ReceivableTokenContractClient<'_>, ReceivableTokenContractClient<'a>>(recv) };
+        let vault = unsafe { core::mem::transmute::<LendingVaultContractClient<'_>, LendingVaultContractClient<'a>>(vault) };
+        let token = unsafe { core::mem::transmute::<TokenClient<'_>, TokenClient<'a>>(token) };
+        let token_admin = unsafe { core::mem::transmute::<StellarAssetClient<'_>, StellarAssetClient<'a>>(token_admin) };
+
+        TestContext { env, client, recv, vault, token, token_admin, admin, verifier, borrower, lp, liquidator }
+    }
+
+    /// Deploy receivable token, vault and borrow contract and wire them to each
+    /// other through `configure`. `base_currency` pins the receivable contract's
+    /// mint currency.
+    fn wire_all<'a>(
+        env: &'a Env,
+        admin: &Address,
+        verifier: &Address,
+        asset: &Address,
+        base_currency: Option<Address>,
+    ) -> (ReceivableTokenContractClient<'a>, LendingVaultContractClient<'a>, BorrowContractClient<'a>) {
+        let recv = ReceivableTokenContractClient::new(env, &env.register(ReceivableTokenContract, ()));
+        let vault = LendingVaultContractClient::new(env, &env.register(LendingVaultContract, ()));
+        let client = BorrowContractClient::new(env, &env.register(BorrowContract, ()));
+
+        recv.initialize(admin, verifier, &base_currency, &false);
+        vault.initialize(admin, asset, &1000_i128, &9000_i128, &1000_i128);
+        client.initialize(admin, &recv.address, &vault.address, &default_config());
+        client.configure(&recv.address, &vault.address);
+        (recv, vault, client)
+    }
+
+    /// Distinct per mint, since a zk_proof_hash can back only one receivable
+    fn proof_hash(ctx: &TestContext) -> BytesN<32> {
+        let mut hash = [2u8; 32];
+        hash[..8].copy_from_slice(&ctx.recv.total_minted().to_be_bytes());
+        BytesN::from_array(&ctx.env, &hash)
+    }
+
+    fn mint_recv(ctx: &TestContext, face_value: i128, risk_score: u32) -> u64 {
+        mint_recv_from(ctx, face_value, risk_score, 0)
+    }
+
+    fn mint_recv_from(ctx: &TestContext, face_value: i128, risk_score: u32, active_from: u64) -> u64 {
+        ctx.recv.mint(
+            &ctx.borrower,
+            &ctx.verifier,
+            &BytesN::from_array(&ctx.env, &[1u8; 32]),
+            &face_value,
+            &ctx.token.address,
+            &50_000_000_u64,
+            &proof_hash(ctx),
+            &risk_score,
+            &String::from_str(&ctx.env, "ipfs://test"),
+            &active_from,
+        )
+    }
+
+    fn mint_recv_in(ctx: &TestContext, face_value: i128, currency: &Address) -> u64 {
+        ctx.recv.mint(
+            &ctx.borrower,
+            &ctx.verifier,
+            &BytesN::from_array(&ctx.env, &[1u8; 32]),
+            &face_value,
+            currency,
+            &50_000_000_u64,
+            &proof_hash(ctx),
+            &0_u32,
+            &String::from_str(&ctx.env, "ipfs://test"),
+            &0_u64,
+        )
+    }
+
+    fn mint_recv_owed_by(ctx: &TestContext, face_value: i128, debtor: u8) -> u64 {
+        ctx.recv.mint(
+            &ctx.borrower,
+            &ctx.verifier,
+            &BytesN::from_array(&ctx.env, &[debtor; 32]),
+            &face_value,
+            &ctx.token.address,
+            &50_000_000_u64,
+            &proof_hash(ctx),
+            &0_u32,
+            &String::from_str(&ctx.env, "ipfs://test"),
+            &0_u64,
+        )
+    }
+
+    fn advance(ctx: &TestContext, seconds: u64) {
+        let now = ctx.env.ledger().timestamp();
+        ctx.env.ledger().set_timestamp(now + seconds);
+    }
+
+    #[test]
+    fn test_preview_matches_liquidation() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let r2 = mint_recv(&ctx, 500_000, 1000);
+        let loan_id = ctx.client.borrow(
+            &ctx.borrower,
+            &soroban_sdk::vec![&ctx.env, r1, r2],
+            &1_000_000,
+            &2_592_000,
+            &None,
+            &None,
+            &None,
+            &None,
+            &None,
+        );
+
+        // Overdue by a day
+        advance(&ctx, 2_592_000 + 86_400);
+
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(preview.receivable_ids, loan.receivable_ids);
+        assert_eq!(preview.receivable_values, soroban_sdk::vec![&ctx.env, 975_000_i128, 475_000_i128]);
+        assert_eq!(preview.collateral_value, 1_450_000);
+        assert!(preview.total_debt > loan.principal);
+        assert_eq!(preview.penalty, preview.total_debt * 500 / 10000);
+        // Overdue for 24h: 24 * 10 bps of escalation off the price
+        assert_eq!(preview.escalation, 240);
+        let price = preview.total_debt + preview.penalty;
+        assert_eq!(preview.recovered, price - price * 240 / 10000);
+        assert_eq!(preview.shortfall, 0);
+
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &None, &None);
+        let (_, _, data) = ctx.env.events().all().last().unwrap();
+        let (ev_id, recovered, shortfall) = <(u64, i128, i128)>::from_val(&ctx.env, &data);
+        assert_eq!(ev_id, loan_id);
+        assert_eq!(recovered, preview.recovered);
+        assert_eq!(shortfall, preview.shortfall);
+        assert_eq!(ctx.recv.get_recv(&r1).owner, ctx.liquidator);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_preview_healthy_loan_not_liquidatable() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        ctx.client.preview_liquidation(&loan_id);
+    }
+
+    #[test]
+    fn test_get_loan_live_matches_views() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &600_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        advance(&ctx, 1_000_000);
+        let view = ctx.client.get_loan_live(&loan_id, &false);
+        assert_eq!(view.current_ltv, ctx.client.get_ltv(&loan_id));
+        assert_eq!(view.is_liquidatable, ctx.client.is_liquidatable(&loan_id));
+        assert_eq!(view.seconds_to_due, 1_592_000);
+        assert_eq!(view.loan.accrued_interest, 0);
+        assert_eq!(view.health_factor, 975_000 * 8500 / view.total_owed);
+
+        // Reading must not accrue; the stored state catches up only on accrue_interest
+        let accrued = ctx.client.accrue_interest(&loan_id);
+        assert_eq!(view.projected_interest, accrued);
+        assert_eq!(view.total_owed, 600_000 + accrued);
+
+        // Refreshed collateral agrees with the origination value under an unchanged config
+        let refreshed = ctx.client.get_loan_live(&loan_id, &true);
+        assert_eq!(refreshed.current_ltv, view.current_ltv);
+
+        advance(&ctx, 2_000_000);
+        let overdue = ctx.client.get_loan_live(&loan_id, &false);
+        assert_eq!(overdue.seconds_to_due, 0);
+        assert!(overdue.is_liquidatable);
+    }
+
+    #[test]
+    fn test_borrow_requires_debtor_ack() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.require_debtor_ack = true;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        let err = ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(err, Err(Ok(Error::RecvNotAcknowledged)));
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Active);
+
+        ctx.recv.acknowledge(&ctx.verifier, &r1, &BytesN::from_array(&ctx.env, &[9u8; 32]));
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).principal, 500_000);
+    }
+
+    #[test]
+    fn test_borrow_without_ack_when_not_required() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Collateralized);
+    }
+
+    #[test]
+    fn test_quote_locks_rate() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        let quote_id = ctx.client.lock_quote(&ctx.borrower, &ids, &500_000, &2_592_000);
+        assert_eq!(ctx.client.get_quote(&quote_id).unwrap().interest_rate, 1200);
+
+        // Quotes don't reserve vault liquidity or lock collateral
+        assert_eq!(ctx.vault.available(), 10_000_000);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Active);
+
+        // Rates move before the borrower signs
+        let mut config = default_config();
+        config.base_interest_rate = 2500;
+        ctx.client.set_config(&config);
+
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &Some(quote_id), &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).interest_rate, 1200);
+        assert!(ctx.client.get_quote(&quote_id).is_none());
+
+        // Without a quote the live rate applies
+        let r2 = mint_recv(&ctx, 1_000_000, 500);
+        let loan2 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan2).interest_rate, 2500);
+    }
+
+    #[test]
+    fn test_quote_mismatch_rejected() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        let quote_id = ctx.client.lock_quote(&ctx.borrower, &ids, &500_000, &2_592_000);
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &600_000, &2_592_000, &Some(quote_id), &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::QuoteMismatch)));
+    }
+
+    #[test]
+    fn test_quote_expires() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        let quote_id = ctx.client.lock_quote(&ctx.borrower, &ids, &500_000, &2_592_000);
+
+        let seq = ctx.env.ledger().sequence();
+        ctx.env.ledger().set_sequence_number(seq + QUOTE_TTL_LEDGERS + 1);
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &Some(quote_id), &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::QuoteExpired)));
+    }
+
+    #[test]
+    fn test_oracle_score_makes_loan_liquidatable() {
+        let ctx = setup();
+        let oracle_id = ctx.env.register(MockRiskOracle, ());
+        let oracle = MockRiskOracleClient::new(&ctx.env, &oracle_id);
+        let mut config = default_config();
+        config.risk_oracle = Some(oracle_id);
+        ctx.client.set_config(&config);
+
+        // Unknown debtor: the oracle traps and the minted score is used
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &682_500, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).collateral_value, 975_000);
+        assert!(!ctx.client.is_liquidatable(&loan_id));
+
+        // A score below the minted one never improves the valuation
+        let debtor = BytesN::from_array(&ctx.env, &[1u8; 32]);
+        oracle.set_score(&debtor, &100);
+        assert_eq!(ctx.client.refresh_collateral(&loan_id), 975_000);
+
+        // Credit deterioration: 10000 * 5000 / 10000 = 50% discount
+        oracle.set_score(&debtor, &10_000);
+        assert!(!ctx.client.is_liquidatable(&loan_id));
+        assert!(ctx.client.get_loan_live(&loan_id, &true).is_liquidatable);
+        assert_eq!(ctx.client.refresh_collateral(&loan_id), 500_000);
+        assert!(ctx.client.is_liquidatable(&loan_id));
+    }
+
+    #[test]
+    fn test_pending_collateral_rejected_until_active() {
+        let ctx = setup();
+        let r1 = mint_recv_from(&ctx, 1_000_000, 500, 1_086_400);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::RecvNotActive)));
+
+        advance(&ctx, 86_400);
+        ctx.recv.activate(&r1);
+        ctx.client.borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Collateralized);
+    }
+
+    #[test]
+    fn test_get_loan_detail_matches_receivable_contract() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let r2 = mint_recv(&ctx, 400_000, 2000);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        let recvs = ctx.client.get_loan_receivables(&loan_id);
+        assert_eq!(recvs.len(), 2);
+        for (i, rid) in [r2, r1].iter().enumerate() {
+            let ours = recvs.get(i as u32).unwrap();
+            let direct = ctx.recv.get_recv(rid);
+            assert_eq!(ours.id, direct.id);
+            assert_eq!(ours.owner, direct.owner);
+            assert_eq!(ours.face_value, direct.face_value);
+            assert_eq!(ours.risk_score, direct.risk_score);
+            assert_eq!(ours.debtor_hash, direct.debtor_hash);
+            assert_eq!(ours.status, ReceivableStatus::Collateralized);
+        }
+
+        let detail = ctx.client.get_loan_detail(&loan_id);
+        assert_eq!(detail.receivables, recvs);
+        assert_eq!(detail.view.loan.id, loan_id);
+        assert_eq!(detail.view.current_ltv, ctx.client.get_ltv(&loan_id));
+    }
+
+    #[test]
+    fn test_get_loan_detail_matches_individual_calls() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let r2 = mint_recv(&ctx, 400_000, 2000);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1, r2], &800_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        // Mid-term, with interest pending, and once overdue
+        for secs in [1_000_000_u64, 2_000_000] {
+            advance(&ctx, secs);
+            let detail = ctx.client.get_loan_detail(&loan_id);
+            let live = ctx.client.get_loan_live(&loan_id, &false);
+            assert_eq!(detail.view.projected_interest, live.projected_interest);
+            assert!(detail.view.projected_interest > 0);
+            assert_eq!(detail.view.current_ltv, ctx.client.get_ltv(&loan_id));
+            assert_eq!(detail.view.is_liquidatable, ctx.client.is_liquidatable(&loan_id));
+            assert_eq!(detail.view.loan.principal, ctx.client.get_loan(&loan_id).principal);
+            for (i, rid) in [r1, r2].iter().enumerate() {
+                let ours = detail.receivables.get(i as u32).unwrap();
+                assert_eq!(ours.id, *rid);
+                assert_eq!(ours.status, ReceivableStatus::Collateralized);
+                assert_eq!(ctx.recv.get_recv(rid).status, receivable_token::ReceivableStatus::Collateralized);
+            }
+        }
+        assert!(ctx.client.get_loan_detail(&loan_id).view.is_liquidatable);
+    }
+
+    #[test]
+    fn test_collateral_basket_capped() {
+        let ctx = setup();
+        let mut ids = Vec::new(&ctx.env);
+        for _ in 0..=MAX_LOAN_RECEIVABLES {
+            ids.push_back(mint_recv(&ctx, 10_000, 500));
+        }
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &1_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::TooManyReceivables)));
+    }
+
+    #[test]
+    fn test_extreme_risk_discount_clamps_to_zero() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 8000);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &300_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        // 8000 * 20000 / 10000 = 16000 bps of discount: previously wrapped to a huge value
+        let mut config = default_config();
+        config.risk_discount_factor = 20_000;
+        ctx.client.set_config(&config);
+        assert_eq!(ctx.client.refresh_collateral(&loan_id), 0);
+        assert_eq!(ctx.client.get_ltv(&loan_id), i128::MAX);
+        assert!(ctx.client.is_liquidatable(&loan_id));
+
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(preview.receivable_values, soroban_sdk::vec![&ctx.env, 0_i128]);
+        assert_eq!(preview.recovered, 0);
+        assert_eq!(preview.shortfall, preview.total_debt);
+
+        // Fully discounted collateral supports no borrowing at all
+        let r2 = mint_recv(&ctx, 1_000_000, 8000);
+        let res = ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &1, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::LTVExceeded)));
+    }
+
+    #[test]
+    fn test_pause_blocks_borrow_only() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let r2 = mint_recv(&ctx, 1_000_000, 500);
+        let loan1 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        let loan2 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        ctx.client.pause();
+        let r3 = mint_recv(&ctx, 1_000_000, 500);
+        let res = ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r3], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::ContractPaused)));
+
+        // Money still comes back in and liquidations still protect LPs
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan1, &500_000), 0);
+        advance(&ctx, 2_592_001);
+        ctx.client.liquidate(&ctx.liquidator, &loan2, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan2).status, LoanStatus::Liquidated);
+
+        ctx.client.unpause();
+        ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r3], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+    }
+
+    #[test]
+    fn test_pause_flags_independent() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        let flags = PauseFlags { borrow: false, repay: true, liquidate: true };
+        ctx.client.set_pause_flags(&flags);
+        assert_eq!(ctx.client.get_pause_flags(), flags);
+        assert_eq!(ctx.client.try_repay_loan(&ctx.borrower, &loan_id, &1_000), Err(Ok(Error::ContractPaused)));
+        advance(&ctx, 2_592_001);
+        assert_eq!(ctx.client.try_liquidate(&ctx.liquidator, &loan_id, &None, &None), Err(Ok(Error::ContractPaused)));
+    }
+
+    #[test]
+    fn test_legacy_pause_flag_still_pauses_borrowing() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        // A contract paused before PauseFlags existed
+        ctx.env.as_contract(&ctx.client.address, || {
+            ctx.env.storage().instance().remove(&DataKey::PauseFlags);
+            ctx.env.storage().instance().set(&DataKey::Paused, &true);
+        });
+        assert_eq!(ctx.client.get_pause_flags(), PauseFlags { borrow: true, repay: false, liquidate: false });
+        let res = ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::ContractPaused)));
+
+        // Any flag update migrates off the legacy key
+        ctx.client.unpause();
+        ctx.env.as_contract(&ctx.client.address, || {
+            assert!(!ctx.env.storage().instance().has(&DataKey::Paused));
+        });
+        ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+    }
+
+    #[test]
+    fn test_get_ltv_overflow_is_clean_error() {
+        let ctx = setup();
+        let huge: i128 = 1_000_000_000_000_000_000_000_000_000_000; // 1e30
+        ctx.token_admin.mint(&ctx.lp, &(huge * 10));
+        ctx.vault.deposit(&ctx.lp, &(huge * 10));
+        let r1 = mint_recv(&ctx, huge * 2, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &huge, &2_592_000, &None, &None, &None, &None, &None);
+
+        // 1e30 * 1200 * 1e7 overflows u128
+        advance(&ctx, 10_000_000);
+        assert_eq!(ctx.client.try_get_ltv(&loan_id), Err(Ok(Error::Overflow)));
+        assert_eq!(ctx.client.try_is_liquidatable(&loan_id), Ok(Ok(true)));
+    }
+
+    #[test]
+    fn test_assume_loan_then_repay() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let r2 = mint_recv(&ctx, 1_000_000, 500);
+        let ids = soroban_sdk::vec![&ctx.env, r1, r2];
+        let factor = Address::generate(&ctx.env);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &800_000, &2_592_000, &None, &None, &None, &None, &Some(factor.clone()));
+        let before = ctx.client.get_loan(&loan_id);
+
+        let buyer = Address::generate(&ctx.env);
+        ctx.client.pause();
+        assert_eq!(ctx.client.try_assume_loan(&loan_id, &buyer), Err(Ok(Error::ContractPaused)));
+        ctx.client.unpause();
+
+        // A transfer restriction the buyer doesn't clear surfaces as a typed error
+        ctx.recv.set_transfer_restricted(&true);
+        assert_eq!(ctx.client.try_assume_loan(&loan_id, &buyer), Err(Ok(Error::RecvError)));
+        ctx.recv.set_transfer_restricted(&false);
+
+        ctx.client.assume_loan(&loan_id, &buyer);
+
+        // The seller's factor has no claim on the buyer's collateral
+        assert_eq!(ctx.client.collateral_recipient(&loan_id), None);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.borrower, buyer);
+        assert_eq!(loan.principal, before.principal);
+        assert_eq!(loan.interest_rate, before.interest_rate);
+        assert_eq!(loan.due_date, before.due_date);
+        assert_eq!(ctx.client.get_borrower_loans(&ctx.borrower).len(), 0);
+        assert_eq!(ctx.client.get_borrower_loans(&buyer), soroban_sdk::vec![&ctx.env, loan_id]);
+        for rid in ids.iter() {
+            let recv = ctx.recv.get_recv(&rid);
+            assert_eq!(recv.owner, buyer);
+            assert_eq!(recv.status, receivable_token::ReceivableStatus::Collateralized);
+        }
+
+        // The seller can no longer repay; the buyer can and gets the collateral back
+        assert_eq!(ctx.client.try_repay_loan(&ctx.borrower, &loan_id, &1_000), Err(Ok(Error::NotBorrower)));
+        advance(&ctx, 86_400);
+        ctx.token_admin.mint(&buyer, &1_000_000);
+        assert_eq!(ctx.client.repay_loan(&buyer, &loan_id, &1_000_000), 0);
+        for rid in ids.iter() {
+            let recv = ctx.recv.get_recv(&rid);
+            assert_eq!(recv.owner, buyer);
+            assert_eq!(recv.status, receivable_token::ReceivableStatus::Active);
+        }
+    }
+
+    #[test]
+    fn test_assume_overdue_loan_blocked() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 2_592_001);
+        let res = ctx.client.try_assume_loan(&loan_id, &Address::generate(&ctx.env));
+        assert_eq!(res, Err(Ok(Error::LoanUnhealthy)));
+    }
+
+    #[test]
+    fn test_liquidation_does_not_overstate_deposits() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.liquidation_penalty = 2000;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &1_000_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 2_592_001);
+
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        let interest = preview.total_debt - 1_000_000;
+        assert_eq!(preview.recovered, preview.total_debt + preview.penalty);
+
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &None, &None);
+        let state = ctx.vault.get_state();
+        assert_eq!(state.total_borrowed, 0);
+
+        // Principal is restored once; only interest + penalty is income, split by the reserve factor
+        let income = interest + preview.penalty;
+        let protocol = income * 1000 / 10000;
+        assert_eq!(state.protocol_reserves, protocol);
+        assert_eq!(state.total_deposits, 10_000_000 + income - protocol);
+    }
+
+    #[test]
+    fn test_liquidation_price_escalates_until_cap() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &1_000_000, &2_592_000, &None, &None, &None, &None, &None);
+        let due = ctx.client.get_loan(&loan_id).due_date;
+
+        // Keeper call records when the loan went overdue
+        advance(&ctx, 2_592_000 + 3_600);
+        ctx.client.accrue_interest(&loan_id);
+        assert_eq!(ctx.client.get_loan(&loan_id).liquidatable_since, due);
+
+        let early = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(early.liquidatable_since, due);
+        assert_eq!(early.escalation, 10);
+
+        advance(&ctx, 5 * 3_600);
+        let later = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(later.escalation, 60);
+        assert!(later.recovered < early.recovered);
+        let price = later.total_debt + later.penalty;
+        assert_eq!(later.recovered, price - price * 60 / 10000);
+
+        // A week on, the discount is pinned at the cap
+        advance(&ctx, 7 * 86_400);
+        let stale = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(stale.escalation, 1000);
+        let price = stale.total_debt + stale.penalty;
+        assert_eq!(stale.recovered, price - price * 1000 / 10000);
+
+        // The liquidator pays exactly the previewed price into the vault
+        let vault_before = ctx.token.balance(&ctx.vault.address);
+        let liq_before = ctx.token.balance(&ctx.liquidator);
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &None, &None);
+        assert_eq!(ctx.token.balance(&ctx.vault.address), vault_before + stale.recovered);
+        assert_eq!(ctx.token.balance(&ctx.liquidator), liq_before - stale.recovered);
+    }
+
+    #[test]
+    fn test_single_debtor_concentration_limit() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.max_single_debtor_bps = 5000;
+        ctx.client.set_config(&config);
+
+        // 60/40 split across two debtors breaches a 50% cap
+        let a1 = mint_recv_owed_by(&ctx, 600_000, 1);
+        let b1 = mint_recv_owed_by(&ctx, 400_000, 2);
+        let res = ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, a1, b1], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::DebtorConcentration)));
+
+        // Receivables from the same debtor are grouped: 300K + 300K vs 400K
+        let a2 = mint_recv_owed_by(&ctx, 300_000, 1);
+        let a3 = mint_recv_owed_by(&ctx, 300_000, 1);
+        let res = ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, a2, a3, b1], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::DebtorConcentration)));
+
+        // Exactly at the cap is allowed
+        let b2 = mint_recv_owed_by(&ctx, 600_000, 2);
+        ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, a2, a3, b2], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        // A single receivable is exempt, and 0 disables the check
+        ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, a1], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+        config.max_single_debtor_bps = 0;
+        ctx.client.set_config(&config);
+        let a4 = mint_recv_owed_by(&ctx, 900_000, 1);
+        ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, a4, b1], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+    }
+
+    #[test]
+    fn test_borrow_against_thin_vault_leaves_collateral_active() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 10_000_000, 0);
+        let r2 = mint_recv(&ctx, 10_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1, r2];
+
+        // 9.5M of a 10M vault breaches the 90% utilization cap
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &9_500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::VaultUtilization)));
+        for rid in ids.iter() {
+            assert_eq!(ctx.recv.get_recv(&rid).status, receivable_token::ReceivableStatus::Active);
+        }
+        assert_eq!(ctx.client.total_loans(), 0);
+
+        ctx.client.borrow(&ctx.borrower, &ids, &9_000_000, &2_592_000, &None, &None, &None, &None, &None);
+    }
+
+    #[test]
+    fn test_accrual_after_due_modes() {
+        let year = SECONDS_PER_YEAR as i128;
+        let term: u64 = 2_592_000;
+        let mut totals = [0i128; 3];
+        for (i, mode) in [AccrualAfterDue::Continue, AccrualAfterDue::PenaltyOnly, AccrualAfterDue::Stop].into_iter().enumerate() {
+            let ctx = setup();
+            let mut config = default_config();
+            config.accrual_after_due = mode;
+            ctx.client.set_config(&config);
+
+            let r1 = mint_recv(&ctx, 2_000_000, 0);
+            let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &1_000_000, &term, &None, &None, &None, &None, &None);
+
+            // Accrue once mid-term so the split has to start from a non-origin update
+            advance(&ctx, term / 2);
+            ctx.client.accrue_interest(&loan_id);
+            advance(&ctx, term / 2 + 30 * 86_400);
+
+            let view = ctx.client.get_loan_live(&loan_id, &false);
+            let preview = ctx.client.preview_liquidation(&loan_id);
+            assert_eq!(preview.total_debt, view.total_owed);
+            ctx.client.accrue_interest(&loan_id);
+            assert_eq!(ctx.client.get_loan(&loan_id).accrued_interest, view.projected_interest);
+            totals[i] = view.total_owed;
+        }
+
+        let interest = |rate: i128, secs: u64| 1_000_000 * rate * secs as i128 / (year * 10000);
+        let half = interest(1200, term / 2);
+        let late = 30 * 86_400;
+        assert_eq!(totals[0], 1_000_000 + half + interest(1200, term / 2 + late));
+        assert_eq!(totals[1], 1_000_000 + 2 * half + interest(2400, late));
+        assert_eq!(totals[2], 1_000_000 + 2 * half);
+        assert!(totals[1] > totals[0] && totals[0] > totals[2]);
+    }
+
+    #[test]
+    fn test_loan_metadata_set_and_update() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let uri = String::from_str(&ctx.env, "ipfs://facility-v1");
+        let ext = BytesN::from_array(&ctx.env, &[7u8; 32]);
+        let loan_id = ctx.client.borrow(
+            &ctx.borrower,
+            &soroban_sdk::vec![&ctx.env, r1],
+            &500_000,
+            &2_592_000,
+            &None,
+            &Some(uri.clone()),
+            &Some(ext.clone()),
+            &None,
+            &None,
+        );
+
+        let (_, _, data) = ctx.env.events().all().last().unwrap();
+        let (ev_id, amount, ev_uri, ev_ref) = <(u64, i128, Option<String>, BytesN<32>)>::from_val(&ctx.env, &data);
+        assert_eq!((ev_id, amount), (loan_id, 500_000));
+        assert_eq!(ev_uri, Some(uri.clone()));
+        assert_eq!(ev_ref, ext);
+
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.metadata_uri, Some(uri));
+        assert_eq!(loan.external_ref, ext);
+
+        // Borrower and admin may update; anyone else may not
+        let v2 = String::from_str(&ctx.env, "ipfs://facility-v2");
+        ctx.client.update_loan_metadata(&ctx.borrower, &loan_id, &v2);
+        assert_eq!(ctx.client.get_loan(&loan_id).metadata_uri, Some(v2.clone()));
+        let v3 = String::from_str(&ctx.env, "ipfs://facility-v3");
+        ctx.client.update_loan_metadata(&ctx.admin, &loan_id, &v3);
+        let res = ctx.client.try_update_loan_metadata(&Address::generate(&ctx.env), &loan_id, &v2);
+        assert_eq!(res, Err(Ok(Error::NotAuthorized)));
+        assert_eq!(ctx.client.get_loan(&loan_id).metadata_uri, Some(v3));
+
+        // Closed loans are frozen
+        ctx.client.repay_loan(&ctx.borrower, &loan_id, &500_000);
+        let res = ctx.client.try_update_loan_metadata(&ctx.borrower, &loan_id, &v2);
+        assert_eq!(res, Err(Ok(Error::InvalidStatus)));
+    }
+
+    #[test]
+    fn test_legacy_loan_record_is_upgraded() {
+        let ctx = setup();
+        let legacy = LegacyLoan {
+            id: 0,
+            borrower: ctx.borrower.clone(),
+            receivable_ids: Vec::new(&ctx.env),
+            collateral_value: 1_000_000,
+            principal: 500_000,
+            interest_rate: 1200,
+            accrued_interest: 0,
+            borrowed_at: 1_000_000,
+            last_interest_update: 1_000_000,
+            due_date: 3_592_000,
+            status: LoanStatus::Active,
+        };
+        ctx.env.as_contract(&ctx.client.address, || {
+            ctx.env.storage().persistent().set(&DataKey::Loan(0), &legacy);
+        });
+
+        let loan = ctx.client.get_loan(&0);
+        assert_eq!(loan.principal, 500_000);
+        assert_eq!(loan.metadata_uri, None);
+        assert_eq!(loan.external_ref, BytesN::from_array(&ctx.env, &[0u8; 32]));
+
+        // The first write moves it to the new key
+        advance(&ctx, 86_400);
+        ctx.client.accrue_interest(&0);
+        ctx.env.as_contract(&ctx.client.address, || {
+            assert!(!ctx.env.storage().persistent().has(&DataKey::Loan(0)));
+            assert!(ctx.env.storage().persistent().has(&DataKey::LoanV2(0)));
+        });
+        assert!(ctx.client.get_loan(&0).accrued_interest > 0);
+    }
+
+    #[test]
+    fn test_repay_from_shares_nets_loan_against_lp_position() {
+        let ctx = setup();
+        ctx.vault.deposit(&ctx.borrower, &800_000);
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        let wallet = ctx.token.balance(&ctx.borrower);
+
+        // Partial netting with a small slice of the position
+        advance(&ctx, 86_400);
+        let remaining = ctx.client.repay_from_shares(&ctx.borrower, &loan_id, &100_000);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(remaining, loan.principal + loan.accrued_interest);
+        assert_eq!(ctx.vault.get_lp(&ctx.borrower).unwrap().shares, 700_000);
+
+        // The rest of the position covers the loan; only what's needed is burned
+        let remaining = ctx.client.repay_from_shares(&ctx.borrower, &loan_id, &700_000);
+        assert_eq!(remaining, 0);
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Repaid);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Active);
+        let left = ctx.vault.get_lp(&ctx.borrower).unwrap().shares;
+        assert!(left > 0 && left < 300_000);
+
+        // No tokens moved through the borrower's wallet
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet);
+        assert_eq!(ctx.vault.get_state().total_borrowed, 0);
+
+        let other = Address::generate(&ctx.env);
+        let r2 = mint_recv(&ctx, 1_000_000, 500);
+        let loan2 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.try_repay_from_shares(&other, &loan2, &1_000), Err(Ok(Error::NotBorrower)));
+    }
+
+    #[test]
+    fn test_sweep_health_emits_one_event_per_live_loan() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let r2 = mint_recv(&ctx, 1_000_000, 500);
+        let r3 = mint_recv(&ctx, 1_000_000, 500);
+        let short = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &86_400, &None, &None, &None, &None, &None);
+        let long = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &600_000, &2_592_000, &None, &None, &None, &None, &None);
+        let repaid = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r3], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+        ctx.client.repay_loan(&ctx.borrower, &repaid, &100_000);
+
+        advance(&ctx, 2 * 86_400);
+        let swept = ctx.client.sweep_health(&soroban_sdk::vec![&ctx.env, short, repaid, long, 99]);
+        assert_eq!(swept, 2);
+
+        let health = symbol_short!("health").into_val(&ctx.env);
+        let mut events = std::vec::Vec::new();
+        for (_, topics, data) in ctx.env.events().all().iter() {
+            if topics.get(0).unwrap().shallow_eq(&health) {
+                let id = u64::from_val(&ctx.env, &topics.get(1).unwrap());
+                events.push((id, <(i128, i128, u64, bool)>::from_val(&ctx.env, &data)));
+            }
+        }
+        assert_eq!(events.len(), 2);
+
+        // Interest was accrued and persisted by the sweep itself
+        for (id, (ltv, owed, to_due, liquidatable)) in events {
+            let loan = ctx.client.get_loan(&id);
+            assert!(loan.accrued_interest > 0);
+            assert_eq!(owed, loan.principal + loan.accrued_interest);
+            assert_eq!(ltv, owed * 10000 / loan.collateral_value);
+            if id == short {
+                assert_eq!((to_due, liquidatable), (0, true));
+                assert_eq!(loan.liquidatable_since, loan.due_date);
+            } else {
+                assert_eq!(id, long);
+                assert_eq!((to_due, liquidatable), (2_592_000 - 2 * 86_400, false));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tranched_loan_accrues_on_drawn_principal_only() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        let tranches = soroban_sdk::vec![&ctx.env, 300_000_i128, 300_000];
+
+        let bad = soroban_sdk::vec![&ctx.env, 300_000_i128, 200_000];
+        assert_eq!(ctx.client.try_borrow_tranched(&ctx.borrower, &ids, &600_000, &bad, &2_592_000), Err(Ok(Error::InvalidTranches)));
+
+        let wallet = ctx.token.balance(&ctx.borrower);
+        let loan_id = ctx.client.borrow_tranched(&ctx.borrower, &ids, &600_000, &tranches, &2_592_000);
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet + 300_000);
+        assert_eq!(ctx.client.get_loan(&loan_id).principal, 300_000);
+
+        advance(&ctx, 1_296_000);
+        assert_eq!(ctx.client.draw(&ctx.borrower, &loan_id), 300_000);
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet + 600_000);
+        assert_eq!(ctx.client.get_tranches(&loan_id).unwrap().next, 2);
+        assert_eq!(ctx.client.try_draw(&ctx.borrower, &loan_id), Err(Ok(Error::NoTrancheRemaining)));
+
+        advance(&ctx, 1_296_000);
+        ctx.client.accrue_interest(&loan_id);
+        let loan = ctx.client.get_loan(&loan_id);
+        let year = SECONDS_PER_YEAR as i128;
+        let first_half = 300_000 * 1200 * 1_296_000 / (year * 10000);
+        let second_half = 600_000 * 1200 * 1_296_000 / (year * 10000);
+        assert_eq!(loan.principal, 600_000);
+        assert_eq!(loan.accrued_interest, first_half + second_half);
+        assert_eq!(ctx.vault.get_state().total_borrowed, 600_000);
+    }
+
+    #[test]
+    fn test_tranche_draw_blocked_by_ltv() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        // Credit line at exactly max LTV; accrued interest leaves no room for the last tranche
+        let tranches = soroban_sdk::vec![&ctx.env, 400_000_i128, 300_000];
+        let loan_id = ctx.client.borrow_tranched(&ctx.borrower, &ids, &700_000, &tranches, &2_592_000);
+
+        advance(&ctx, 86_400);
+        assert_eq!(ctx.client.try_draw(&ctx.borrower, &loan_id), Err(Ok(Error::LTVExceeded)));
+        assert_eq!(ctx.client.get_tranches(&loan_id).unwrap().next, 1);
+    }
+
+    #[test]
+    fn test_ltv_warning_emitted_once_and_cleared() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.warning_threshold = 8500;
+        assert_eq!(ctx.client.try_set_config(&config), Err(Ok(Error::InvalidConfig)));
+        config.warning_threshold = 7050;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &700_000, &5_184_000, &None, &None, &None, &None, &None);
+
+        let warn = symbol_short!("ltv_warn").into_val(&ctx.env);
+        let warn_events = |ctx: &TestContext| {
+            ctx.env.events().all().iter().filter(|(_, topics, _)| topics.get(0).unwrap().shallow_eq(&warn)).count()
+        };
+
+        // 70% -> above 70.5% purely from accrued interest
+        advance(&ctx, 86_400 * 40);
+        ctx.client.accrue_interest(&loan_id);
+        assert_eq!(warn_events(&ctx), 1);
+        let view = ctx.client.get_loan_live(&loan_id, &false);
+        assert!(view.loan.warned);
+        assert!(view.current_ltv > 7050);
+
+        // Still over the line: no second event
+        advance(&ctx, 86_400);
+        ctx.client.accrue_interest(&loan_id);
+        assert_eq!(warn_events(&ctx), 0);
+        assert!(ctx.client.get_loan(&loan_id).warned);
+
+        // Just under the threshold is inside the hysteresis band
+        let owed = ctx.client.get_loan_live(&loan_id, &false).total_owed;
+        ctx.client.repay_loan(&ctx.borrower, &loan_id, &(owed - 690_000));
+        assert!(ctx.client.get_loan(&loan_id).warned);
+
+        // Well below clears it
+        ctx.client.repay_loan(&ctx.borrower, &loan_id, &100_000);
+        assert!(!ctx.client.get_loan_live(&loan_id, &false).loan.warned);
+    }
+
+    #[test]
+    fn test_vault_errors_are_mapped() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+
+        ctx.vault.pause();
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::VaultPaused)));
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Active);
+        ctx.vault.unpause();
+
+        // Drain the vault's cash so liquidity, not utilization, is the binding limit
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        let r3 = mint_recv(&ctx, 1_000_000, 500);
+        let tranches = soroban_sdk::vec![&ctx.env, 100_000_i128, 100_000];
+        let tranched = ctx.client.borrow_tranched(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r3], &200_000, &tranches, &2_592_000);
+        let lp_shares = ctx.vault.get_lp(&ctx.lp).unwrap().shares;
+        ctx.vault.withdraw(&ctx.lp, &(lp_shares - 600_000));
+        let r2 = mint_recv(&ctx, 1_000_000, 500);
+        let res = ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::VaultLiquidity)));
+
+        // Tranche draws map the vault's refusals the same way
+        assert_eq!(ctx.client.try_draw(&ctx.borrower, &tranched), Err(Ok(Error::VaultLiquidity)));
+        ctx.vault.pause();
+        assert_eq!(ctx.client.try_draw(&ctx.borrower, &tranched), Err(Ok(Error::VaultPaused)));
+        ctx.vault.unpause();
+        assert_eq!(ctx.client.get_loan(&tranched).principal, 100_000);
+
+        // A repayment the borrower can't fund comes back as a borrow-contract error
+        let broke = ctx.token.balance(&ctx.borrower);
+        ctx.token.transfer(&ctx.borrower, &ctx.lp, &broke);
+        let res = ctx.client.try_repay_loan(&ctx.borrower, &loan_id, &100_000);
+        assert_eq!(res, Err(Ok(Error::VaultRejected)));
+    }
+
+    #[test]
+    fn test_borrower_stats_track_lifecycle() {
+        let ctx = setup();
+        let month = 2_592_000_u64;
+
+        // On time
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let l1 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &month, &None, &None, &None, &None, &None);
+        advance(&ctx, 86_400);
+        ctx.client.repay_loan(&ctx.borrower, &l1, &600_000);
+        let i1 = 500_000 * 1200 * 86_400 / (10000 * 31_536_000);
+
+        // Late
+        let r2 = mint_recv(&ctx, 2_000_000, 0);
+        let l2 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &400_000, &month, &None, &None, &None, &None, &None);
+        advance(&ctx, month + 60);
+        let owed = ctx.client.get_loan_live(&l2, &false).total_owed;
+        ctx.client.repay_loan(&ctx.borrower, &l2, &owed);
+
+        // Liquidated
+        let r3 = mint_recv(&ctx, 2_000_000, 0);
+        let l3 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r3], &300_000, &month, &None, &None, &None, &None, &None);
+        let mid = ctx.client.get_borrower_stats(&ctx.borrower);
+        assert_eq!(mid.outstanding, 300_000);
+        advance(&ctx, month + 60);
+        ctx.client.liquidate(&ctx.liquidator, &l3, &None, &None);
+
+        let stats = ctx.client.get_borrower_stats(&ctx.borrower);
+        assert_eq!(stats, BorrowerStats {
+            loans_opened: 3,
+            repaid_on_time: 1,
+            repaid_late: 1,
+            liquidated: 1,
+            total_borrowed: 1_200_000,
+            interest_paid: i1 + (owed - 400_000),
+            outstanding: 0,
+        });
+        assert_eq!(ctx.client.get_borrower_stats(&ctx.liquidator), BorrowerStats::default());
+    }
+
+    #[test]
+    fn test_force_liquidate_healthy_loan() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 3_600);
+
+        // Healthy: the normal path still refuses, and force needs a recovery address
+        assert_eq!(ctx.client.try_liquidate(&ctx.liquidator, &loan_id, &None, &None), Err(Ok(Error::NotLiquidatable)));
+        assert_eq!(ctx.client.try_force_liquidate(&loan_id, &7), Err(Ok(Error::NoRecoveryAddress)));
+
+        let recovery = Address::generate(&ctx.env);
+        ctx.client.set_recovery_address(&recovery);
+        ctx.token_admin.mint(&recovery, &1_000_000);
+        let owed = ctx.client.get_loan_live(&loan_id, &false).total_owed;
+        let recovered = owed + owed * default_config().liquidation_penalty / 10000;
+        ctx.client.force_liquidate(&loan_id, &7);
+
+        let (_, topics, data) = ctx.env.events().all().last().unwrap();
+        assert!(topics.get(0).unwrap().shallow_eq(&symbol_short!("f_liq").into_val(&ctx.env)));
+        assert_eq!(u64::from_val(&ctx.env, &topics.get(1).unwrap()), loan_id);
+        assert_eq!(<(u32, Address, i128, i128)>::from_val(&ctx.env, &data), (7, recovery.clone(), owed, recovered));
+
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Liquidated);
+        let recv = ctx.recv.get_recv(&r1);
+        assert_eq!(recv.owner, recovery);
+        assert_eq!(recv.status, receivable_token::ReceivableStatus::Active);
+
+        // The recovery address paid the liquidation price: principal back in
+        // full, interest and penalty split like any liquidation income
+        assert_eq!(ctx.token.balance(&recovery), 1_000_000 - recovered);
+        let income = recovered - 500_000;
+        let state = ctx.vault.get_state();
+        assert_eq!(state.total_borrowed, 0);
+        assert_eq!(state.total_deposits, 10_000_000 + income - income * 1000 / 10000);
+        assert_eq!(ctx.client.get_borrower_stats(&ctx.borrower).liquidated, 1);
+    }
+
+    #[test]
+    fn test_force_liquidate_overdue_loan() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        let recovery = Address::generate(&ctx.env);
+        ctx.client.set_recovery_address(&recovery);
+        ctx.token_admin.mint(&recovery, &1_000_000);
+
+        // Past due: open to `liquidate`, but fraud evidence shouldn't have to wait for it
+        advance(&ctx, 2_592_000 + 86_400);
+        assert!(ctx.client.preview_liquidation(&loan_id).liquidatable_since > 0);
+        ctx.client.force_liquidate(&loan_id, &7);
+
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Liquidated);
+        assert_eq!(ctx.recv.get_recv(&r1).owner, recovery);
+        assert_eq!(ctx.vault.get_state().total_borrowed, 0);
+    }
+
+    #[test]
+    fn test_late_fee_charged_once() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.late_fee_bps = 200;
+        ctx.client.set_config(&config);
+
+        let term: u64 = 2_592_000;
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &1_000_000, &term, &None, &None, &None, &None, &None);
+        let interest = |secs: u64| 1_000_000 * 1200 * secs as i128 / (SECONDS_PER_YEAR as i128 * 10000);
+
+        // Accruing exactly at maturity isn't overdue yet
+        advance(&ctx, term);
+        assert_eq!(ctx.client.accrue_interest(&loan_id), interest(term));
+
+        advance(&ctx, 60);
+        let with_fee = ctx.client.accrue_interest(&loan_id);
+        assert_eq!(with_fee, interest(term) + interest(60) + 20_000);
+
+        // Later accruals only add interest
+        advance(&ctx, 86_400);
+        let later = ctx.client.accrue_interest(&loan_id);
+        assert_eq!(later - with_fee, interest(86_400));
+
+        let mut bad = default_config();
+        bad.late_fee_bps = 10_001;
+        assert_eq!(ctx.client.try_set_config(&bad), Err(Ok(Error::InvalidConfig)));
+    }
+
+    #[test]
+    fn test_config_change_does_not_reach_existing_loan() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &1_000_000, &2_592_000, &None, &None, &None, &None, &None);
+        let original = ctx.client.get_loan(&loan_id).terms;
+        assert_eq!(original, LoanTerms::from_config(&default_config()));
+
+        // Tighten everything: at 4000 bps the 50% LTV loan would be liquidatable
+        let mut config = default_config();
+        config.liquidation_threshold = 4000;
+        config.liquidation_penalty = 2000;
+        config.accrual_after_due = AccrualAfterDue::Stop;
+        ctx.client.set_config(&config);
+
+        advance(&ctx, 86_400);
+        assert!(!ctx.client.is_liquidatable(&loan_id));
+        assert!(!ctx.client.get_loan_live(&loan_id, &false).is_liquidatable);
+        assert_eq!(ctx.client.try_liquidate(&ctx.liquidator, &loan_id, &None, &None), Err(Ok(Error::NotLiquidatable)));
+
+        // Once overdue it liquidates on its own penalty and keeps accruing under `Continue`
+        advance(&ctx, 2_592_000);
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        let interest = 1_000_000 * 1200 * (2_592_000 + 86_400) / (SECONDS_PER_YEAR as i128 * 10000);
+        assert_eq!(preview.total_debt, 1_000_000 + interest);
+        assert_eq!(preview.penalty, preview.total_debt * 500 / 10000);
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).terms, original);
+
+        // New originations pick up the new config
+        let r2 = mint_recv(&ctx, 2_000_000, 0);
+        let fresh = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&fresh).terms, LoanTerms::from_config(&config));
+    }
+
+    #[test]
+    fn test_collateral_factor_per_currency() {
+        let ctx = setup();
+        let usd = ctx.token.address.clone();
+        let eur = Address::generate(&ctx.env);
+        ctx.client.set_collateral_factor(&usd, &Some(5000));
+        ctx.client.set_collateral_factor(&eur, &Some(8000));
+        assert_eq!(ctx.client.try_set_collateral_factor(&eur, &Some(10_001)), Err(Ok(Error::InvalidConfig)));
+
+        let month = 2_592_000_u64;
+        let r_usd = mint_recv_in(&ctx, 1_000_000, &usd);
+        let ids = soroban_sdk::vec![&ctx.env, r_usd];
+        assert_eq!(ctx.client.try_borrow(&ctx.borrower, &ids, &500_001, &month, &None, &None, &None, &None, &None), Err(Ok(Error::LTVExceeded)));
+        ctx.client.borrow(&ctx.borrower, &ids, &500_000, &month, &None, &None, &None, &None, &None);
+
+        // Same face value in the other currency backs more than the 70% default
+        let r_eur = mint_recv_in(&ctx, 1_000_000, &eur);
+        let ids = soroban_sdk::vec![&ctx.env, r_eur];
+        assert_eq!(ctx.client.try_borrow(&ctx.borrower, &ids, &800_001, &month, &None, &None, &None, &None, &None), Err(Ok(Error::LTVExceeded)));
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &800_000, &month, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).terms.max_ltv, 8000);
+
+        // Mixed baskets are capped per receivable
+        let a = mint_recv_in(&ctx, 1_000_000, &usd);
+        let b = mint_recv_in(&ctx, 1_000_000, &eur);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, a, b], &1_300_000, &month, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).terms.max_ltv, 6500);
+
+        // Clearing a factor falls back to max_ltv
+        ctx.client.set_collateral_factor(&eur, &None);
+        assert_eq!(ctx.client.get_collateral_factor(&eur), 7000);
+    }
+
+    #[test]
+    fn test_collateral_seasoning() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.min_collateral_age_secs = 86_400;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::CollateralTooNew)));
+        assert_eq!(ctx.client.try_lock_quote(&ctx.borrower, &ids, &500_000, &2_592_000), Err(Ok(Error::CollateralTooNew)));
+
+        advance(&ctx, 86_399);
+        let res = ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::CollateralTooNew)));
+
+        advance(&ctx, 1);
+        ctx.client.borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+    }
+
+    #[test]
+    fn test_stale_collateral_revalued_before_liquidation_checks() {
+        let ctx = setup();
+        let oracle_id = ctx.env.register(MockRiskOracle, ());
+        let oracle = MockRiskOracleClient::new(&ctx.env, &oracle_id);
+        let mut config = default_config();
+        config.risk_oracle = Some(oracle_id);
+        config.max_staleness_secs = 86_400;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &682_500, &2_592_000, &None, &None, &None, &None, &None);
+        let opened = ctx.env.ledger().timestamp();
+        assert_eq!(ctx.client.get_loan(&loan_id).collateral_refreshed_at, opened);
+
+        // Score jumps, but a fresh valuation is trusted as-is
+        oracle.set_score(&BytesN::from_array(&ctx.env, &[1u8; 32]), &10_000);
+        advance(&ctx, 3_600);
+        assert!(!ctx.client.is_liquidatable(&loan_id));
+        assert_eq!(ctx.client.try_preview_liquidation(&loan_id), Err(Ok(Error::NotLiquidatable)));
+
+        // An explicit refresh picks it up and reports the delta
+        assert_eq!(ctx.client.refresh_collateral(&loan_id), 500_000);
+        let (_, _, data) = ctx.env.events().all().last().unwrap();
+        assert_eq!(<(i128, i128, i128)>::from_val(&ctx.env, &data), (975_000, 500_000, -475_000));
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.collateral_refreshed_at, opened + 3_600);
+        assert!(ctx.client.is_liquidatable(&loan_id));
+
+        // Second loan, never refreshed: once stale, the checks re-price inline
+        let r2 = mint_recv_owed_by(&ctx, 1_000_000, 9);
+        let loan2 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &700_000, &2_592_000, &None, &None, &None, &None, &None);
+        oracle.set_score(&BytesN::from_array(&ctx.env, &[9u8; 32]), &10_000);
+        assert!(!ctx.client.is_liquidatable(&loan2));
+        advance(&ctx, 86_401);
+        assert!(ctx.client.is_liquidatable(&loan2));
+        assert!(ctx.client.get_loan_live(&loan2, &false).is_liquidatable);
+        let preview = ctx.client.preview_liquidation(&loan2);
+        assert_eq!(preview.collateral_value, 500_000);
+        ctx.client.liquidate(&ctx.liquidator, &loan2, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan2).collateral_value, 500_000);
+    }
+
+    #[test]
+    fn test_overdue_loans_view() {
+        let ctx = setup();
+        let day: u64 = 86_400;
+        let open = |face: i128, amount: i128, days: u64| {
+            let rid = mint_recv(&ctx, face, 0);
+            ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, rid], &amount, &(days * day), &None, &None, &None, &None, &None)
+        };
+        let a = open(2_000_000, 1_000_000, 10);
+        let b = open(2_000_000, 600_000, 20);
+        let c = open(2_000_000, 400_000, 40);
+        let d = open(2_000_000, 300_000, 15);
+        ctx.client.repay_loan(&ctx.borrower, &d, &300_000);
+
+        assert_eq!(ctx.client.get_overdue_loans(&0, &10).len(), 0);
+        assert_eq!(ctx.client.overdue_principal(), 0);
+
+        // Day 25: a and b are past due, c is not, d was repaid
+        advance(&ctx, 25 * day);
+        let ids = |views: Vec<LoanView>| {
+            let mut out = Vec::new(&ctx.env);
+            for v in views.iter() { out.push_back(v.loan.id); }
+            out
+        };
+        assert_eq!(ids(ctx.client.get_overdue_loans(&0, &10)), soroban_sdk::vec![&ctx.env, a, b]);
+        assert_eq!(ids(ctx.client.get_overdue_loans(&0, &1)), soroban_sdk::vec![&ctx.env, a]);
+        assert_eq!(ids(ctx.client.get_overdue_loans(&1, &10)), soroban_sdk::vec![&ctx.env, b]);
+        assert_eq!(ctx.client.overdue_principal(), 1_600_000);
+        let first = ctx.client.get_overdue_loans(&0, &1).get(0).unwrap();
+        assert!(first.is_liquidatable);
+        assert!(first.projected_interest > 0);
+
+        // Liquidating takes a loan off the overdue set; c joins once it matures
+        ctx.client.liquidate(&ctx.liquidator, &a, &None, &None);
+        advance(&ctx, 20 * day);
+        assert_eq!(ids(ctx.client.get_overdue_loans(&0, &10)), soroban_sdk::vec![&ctx.env, b, c]);
+        assert_eq!(ctx.client.overdue_principal(), 1_000_000);
+    }
+
+    #[test]
+    fn test_interest_only_period_defers_principal() {
+        let ctx = setup();
+        let day: u64 = 86_400;
+        let mut config = default_config();
+        config.max_interest_only_secs = 60 * day;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &ids, &1_000_000, &(90 * day), &None, &None, &None, &Some(61 * day), &None),
+            Err(Ok(Error::InvalidDuration))
+        );
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &1_000_000, &(90 * day), &None, &None, &None, &Some(60 * day), &None);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.interest_only_until, loan.borrowed_at + 60 * day);
+        let interest = |secs: u64| 1_000_000 * 1200 * secs as i128 / (SECONDS_PER_YEAR as i128 * 10000);
+
+        // Inside the window a partial payment only clears interest
+        advance(&ctx, 30 * day);
+        let before = ctx.token.balance(&ctx.borrower);
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan_id, &500_000), 1_000_000);
+        assert_eq!(before - ctx.token.balance(&ctx.borrower), interest(30 * day));
+        assert_eq!(ctx.client.get_loan(&loan_id).principal, 1_000_000);
+        assert_eq!(ctx.client.try_repay_loan(&ctx.borrower, &loan_id, &100), Err(Ok(Error::InterestOnlyPeriod)));
+
+        // After it, the usual interest-then-principal waterfall applies
+        advance(&ctx, 31 * day);
+        let remaining = ctx.client.repay_loan(&ctx.borrower, &loan_id, &500_000);
+        assert_eq!(remaining, 1_000_000 + interest(31 * day) - 500_000);
+        assert_eq!(ctx.client.get_loan(&loan_id).principal, remaining);
+    }
+
+    #[test]
+    fn test_max_risk_score_rejects_and_excludes() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.max_risk_score = 7000;
+        ctx.client.set_config(&config);
+
+        let safe = mint_recv(&ctx, 1_000_000, 500);
+        let risky = mint_recv(&ctx, 1_000_000, 8000);
+        let basket = soroban_sdk::vec![&ctx.env, safe, risky];
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &basket, &100_000, &2_592_000, &None, &None, &None, &None, &None),
+            Err(Ok(Error::RiskTooHigh))
+        );
+        assert_eq!(
+            ctx.client.try_lock_quote(&ctx.borrower, &basket, &100_000, &2_592_000),
+            Err(Ok(Error::RiskTooHigh))
+        );
+
+        // The quote drops the risky receivable rather than failing
+        let cap = ctx.client.max_borrowable(&ctx.borrower, &basket);
+        assert_eq!(cap.excluded, soroban_sdk::vec![&ctx.env, risky]);
+        assert_eq!(cap.collateral_value, 975_000);
+        assert_eq!(cap.max_borrow, 682_500);
+
+        let ok = soroban_sdk::vec![&ctx.env, safe];
+        ctx.client.borrow(&ctx.borrower, &ok, &cap.max_borrow, &2_592_000, &None, &None, &None, &None, &None);
+    }
+
+    #[test]
+    fn test_min_repay_blocks_dust_but_not_payoff() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.min_repay = 10_000;
+        config.min_repay_bps = 100;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        // 1% of 500K owed is the binding minimum here
+        assert_eq!(ctx.client.try_repay_loan(&ctx.borrower, &loan_id, &1), Err(Ok(Error::RepayTooSmall)));
+        assert_eq!(ctx.client.try_repay_loan(&ctx.borrower, &loan_id, &4_999), Err(Ok(Error::RepayTooSmall)));
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan_id, &495_000), 5_000);
+
+        // Clearing a remainder below the minimum is still allowed
+        assert_eq!(ctx.client.try_repay_loan(&ctx.borrower, &loan_id, &4_000), Err(Ok(Error::RepayTooSmall)));
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan_id, &5_000), 0);
+
+        let mut bad = default_config();
+        bad.min_repay_bps = 10_001;
+        assert_eq!(ctx.client.try_set_config(&bad), Err(Ok(Error::InvalidConfig)));
+    }
+
+    #[test]
+    fn test_liquidation_seizes_only_covering_receivables() {
+        let ctx = setup();
+        let small = mint_recv(&ctx, 300_000, 0);
+        let large = mint_recv(&ctx, 900_000, 0);
+        let mid = mint_recv(&ctx, 400_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, small, large, mid];
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        // Overdue: ~530K of debt plus penalty needs the two cheapest receivables
+        advance(&ctx, 2_592_000 + 1);
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        assert!(preview.total_debt + preview.penalty > 300_000);
+        assert!(preview.total_debt + preview.penalty <= 700_000);
+        assert_eq!(preview.seized, soroban_sdk::vec![&ctx.env, small, mid]);
+
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &None, &None);
+        assert_eq!(ctx.recv.get_recv(&small).owner, ctx.liquidator);
+        assert_eq!(ctx.recv.get_recv(&mid).owner, ctx.liquidator);
+        let kept = ctx.recv.get_recv(&large);
+        assert_eq!(kept.owner, ctx.borrower);
+        assert_eq!(kept.status, receivable_token::ReceivableStatus::Active);
+    }
+
+    #[test]
+    fn test_liquidation_needs_no_borrower_signature() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let r2 = mint_recv(&ctx, 2_000_000, 0);
+        let overdue = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        let flagged = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &500_000, &5_184_000, &None, &None, &None, &None, &None);
+        let recovery = Address::generate(&ctx.env);
+        ctx.client.set_recovery_address(&recovery);
+        ctx.token_admin.mint(&recovery, &1_000_000);
+        advance(&ctx, 2_592_000 + 1);
+
+        // Only the liquidator signs: its call and the payment into the vault
+        let recovered = ctx.client.preview_liquidation(&overdue).recovered;
+        ctx.env.mock_auths(&[MockAuth {
+            address: &ctx.liquidator,
+            invoke: &MockAuthInvoke {
+                contract: &ctx.client.address,
+                fn_name: "liquidate",
+                args: (&ctx.liquidator, overdue, None::<i128>, None::<i128>).into_val(&ctx.env),
+                sub_invokes: &[MockAuthInvoke {
+                    contract: &ctx.token.address,
+                    fn_name: "transfer",
+                    args: (&ctx.liquidator, &ctx.vault.address, recovered).into_val(&ctx.env),
+                    sub_invokes: &[],
+                }],
+            },
+        }]);
+        ctx.client.liquidate(&ctx.liquidator, &overdue, &None, &None);
+        assert_eq!(ctx.recv.get_recv(&r1).owner, ctx.liquidator);
+
+        // Only the admin and the recovery address sign a forced seizure
+        let owed = ctx.client.get_loan_live(&flagged, &false).total_owed;
+        let recovered = owed + owed * default_config().liquidation_penalty / 10000;
+        let force = MockAuthInvoke {
+            contract: &ctx.client.address,
+            fn_name: "force_liquidate",
+            args: (flagged, 3_u32).into_val(&ctx.env),
+            sub_invokes: &[],
+        };
+        ctx.env.mock_auths(&[
+            MockAuth { address: &ctx.admin, invoke: &force },
+            MockAuth {
+                address: &recovery,
+                invoke: &MockAuthInvoke {
+                    sub_invokes: &[MockAuthInvoke {
+                        contract: &ctx.token.address,
+                        fn_name: "transfer",
+                        args: (&recovery, &ctx.vault.address, recovered).into_val(&ctx.env),
+                        sub_invokes: &[],
+                    }],
+                    ..force.clone()
+                },
+            },
+        ]);
+        ctx.client.force_liquidate(&flagged, &3);
+        assert_eq!(ctx.recv.get_recv(&r2).owner, recovery);
+        assert_eq!(ctx.client.get_loan(&flagged).status, LoanStatus::Liquidated);
+    }
+
+    #[test]
+    fn test_liquidation_seizes_everything_when_short() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 400_000, 5000);
+        let r2 = mint_recv(&ctx, 400_000, 5000);
+        let ids = soroban_sdk::vec![&ctx.env, r1, r2];
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &420_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        // A harsher discount leaves the basket worth 400K against 420K+ of debt
+        let mut config = default_config();
+        config.risk_discount_factor = 10_000;
+        ctx.client.set_config(&config);
+        advance(&ctx, 2_592_000 + 1);
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(preview.receivable_values, soroban_sdk::vec![&ctx.env, 200_000, 200_000]);
+        assert_eq!(preview.seized, ids);
+
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &None, &None);
+        assert_eq!(ctx.recv.get_recv(&r1).owner, ctx.liquidator);
+        assert_eq!(ctx.recv.get_recv(&r2).owner, ctx.liquidator);
+    }
+
+    #[test]
+    fn test_loan_entries_outlive_default_ttl() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let term: u64 = 90 * 86_400;
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &term, &None, &None, &None, &None, &None);
+
+        let ttl = |key: DataKey| ctx.env.as_contract(&ctx.client.address, || ctx.env.storage().persistent().get_ttl(&key));
+        let expected = (term / SECS_PER_LEDGER) as u32 + LOAN_TTL_MARGIN_LEDGERS;
+        assert_eq!(ttl(DataKey::LoanV2(loan_id)), expected);
+        assert_eq!(ttl(DataKey::BorrowerLoans(ctx.borrower.clone())), expected);
+        assert_eq!(ttl(DataKey::OpenLoans), expected);
+
+        // Ledgers close faster than assumed: 2M ledgers in half the term
+        // leaves the entry well past the 10-ledger default but close to expiry
+        let seq = ctx.env.ledger().sequence();
+        ctx.env.ledger().set_sequence_number(seq + 2_000_000);
+        advance(&ctx, term / 2);
+        assert_eq!(ttl(DataKey::LoanV2(loan_id)), expected - 2_000_000);
+
+        // A keeper bump pushes it back out to due date plus margin, so the
+        // loan survives ledgers that would otherwise have archived it
+        ctx.client.bump_loan(&loan_id);
+        let bumped = (term / 2 / SECS_PER_LEDGER) as u32 + LOAN_TTL_MARGIN_LEDGERS;
+        assert_eq!(ttl(DataKey::LoanV2(loan_id)), bumped);
+        ctx.env.ledger().set_sequence_number(seq + 2_000_000 + expected - 1_000_000);
+        assert_eq!(ctx.client.get_loan(&loan_id).principal, 500_000);
+        assert_eq!(ctx.client.get_config().max_ltv, default_config().max_ltv);
+    }
+
+    #[test]
+    fn test_debtor_exposure_apportioned_by_collateral() {
+        let ctx = setup();
+        let d1 = BytesN::from_array(&ctx.env, &[1u8; 32]);
+        let d2 = BytesN::from_array(&ctx.env, &[2u8; 32]);
+
+        // 70/30 split of collateral between two debtors
+        let r1 = mint_recv_owed_by(&ctx, 700_000, 1);
+        let r2 = mint_recv_owed_by(&ctx, 300_000, 2);
+        let loan_a = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1, r2], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.exposure_of(&d1), 350_000);
+        assert_eq!(ctx.client.exposure_of(&d2), 150_000);
+
+        // Repayments shrink it pro rata; other loans on the same debtor add up
+        ctx.client.repay_loan(&ctx.borrower, &loan_a, &100_000);
+        assert_eq!(ctx.client.exposure_of(&d1), 280_000);
+        assert_eq!(ctx.client.exposure_of(&d2), 120_000);
+        let r3 = mint_recv_owed_by(&ctx, 500_000, 1);
+        let loan_b = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r3], &200_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.exposure_of(&d1), 480_000);
+
+        // Closing a loan releases its share
+        ctx.client.repay_loan(&ctx.borrower, &loan_a, &400_000);
+        assert_eq!(ctx.client.exposure_of(&d1), 200_000);
+        assert_eq!(ctx.client.exposure_of(&d2), 0);
+        advance(&ctx, 2_592_001);
+        ctx.client.liquidate(&ctx.liquidator, &loan_b, &None, &None);
+        assert_eq!(ctx.client.exposure_of(&d1), 0);
+    }
+
+    #[test]
+    fn test_borrow_against_matured_receivable_with_haircut() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.matured_haircut_bps = 2000;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let r2 = mint_recv(&ctx, 1_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        assert_eq!(ctx.client.max_borrowable(&ctx.borrower, &ids).max_borrow, 700_000);
+
+        // Past due but unsettled
+        let maturity = ctx.recv.get_recv(&r1).maturity_date;
+        ctx.env.ledger().set_timestamp(maturity + 5 * 86_400);
+        ctx.recv.mature(&r1);
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &ids, &100_000, &2_592_000, &None, &None, &None, &None, &None),
+            Err(Ok(Error::RecvNotActive))
+        );
+
+        config.allow_matured_collateral = true;
+        ctx.client.set_config(&config);
+        let cap = ctx.client.max_borrowable(&ctx.borrower, &ids);
+        assert_eq!(cap.collateral_value, 800_000);
+        assert_eq!(cap.max_borrow, 560_000);
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &ids, &560_001, &2_592_000, &None, &None, &None, &None, &None),
+            Err(Ok(Error::LTVExceeded))
+        );
+
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &560_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).collateral_value, 800_000);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Collateralized);
+
+        // Defaulted receivables stay unacceptable
+        ctx.recv.mark_default(&r2);
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &100_000, &2_592_000, &None, &None, &None, &None, &None),
+            Err(Ok(Error::RecvNotActive))
+        );
+    }
+
+    #[test]
+    fn test_single_receivable_concentration_limit() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.max_single_collateral_bps = 4000;
+        ctx.client.set_config(&config);
+
+        // One invoice is 70% of the basket, even though all share a debtor
+        let big = mint_recv(&ctx, 700_000, 0);
+        let s1 = mint_recv(&ctx, 150_000, 0);
+        let s2 = mint_recv(&ctx, 150_000, 0);
+        let res = ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, big, s1, s2], &100_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(res, Err(Ok(Error::CollateralConcentration)));
+
+        // Shares are measured on discounted value: 400K at risk 5000 is worth 300K
+        let risky = mint_recv(&ctx, 400_000, 5000);
+        let m1 = mint_recv(&ctx, 250_000, 0);
+        let m2 = mint_recv(&ctx, 200_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, risky, m1, m2, s1];
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &100_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.get_loan(&loan_id).collateral_value, 900_000);
+
+        let mut bad = default_config();
+        bad.max_single_collateral_bps = 10_001;
+        assert_eq!(ctx.client.try_set_config(&bad), Err(Ok(Error::InvalidConfig)));
+    }
+
+    #[test]
+    fn test_liquidation_slippage_guards() {
+        let ctx = setup();
+        let small = mint_recv(&ctx, 300_000, 0);
+        let large = mint_recv(&ctx, 900_000, 0);
+        let mid = mint_recv(&ctx, 400_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, small, large, mid];
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 2_592_000 + 1);
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(preview.seized_value, 700_000);
+
+        // Interest accrues within the same escalation hour, so the price rises
+        advance(&ctx, 600);
+        let res = ctx.client.try_liquidate(&ctx.liquidator, &loan_id, &None, &Some(preview.recovered));
+        assert_eq!(res, Err(Ok(Error::SlippageExceeded)));
+
+        // A partial repayment shrinks the debt, so only the smallest receivable is seized
+        ctx.client.repay_loan(&ctx.borrower, &loan_id, &300_000);
+        let res = ctx.client.try_liquidate(&ctx.liquidator, &loan_id, &Some(preview.seized_value), &None);
+        assert_eq!(res, Err(Ok(Error::SlippageExceeded)));
+
+        let fresh = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(fresh.seized, soroban_sdk::vec![&ctx.env, small]);
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &Some(fresh.seized_value), &Some(fresh.recovered));
+        assert_eq!(ctx.recv.get_recv(&small).owner, ctx.liquidator);
+        assert_eq!(ctx.recv.get_recv(&mid).owner, ctx.borrower);
+    }
+
+    #[test]
+    fn test_zero_coupon_owes_fixed_amount() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+
+        // The repayment, not the proceeds, is what must fit under max_ltv
+        assert_eq!(
+            ctx.client.try_borrow_discount(&ctx.borrower, &ids, &650_000, &720_000, &7_776_000),
+            Err(Ok(Error::LTVExceeded))
+        );
+        assert_eq!(
+            ctx.client.try_borrow_discount(&ctx.borrower, &ids, &500_000, &499_999, &7_776_000),
+            Err(Ok(Error::InvalidRepayAmount))
+        );
+
+        let before = ctx.token.balance(&ctx.borrower);
+        let loan_id = ctx.client.borrow_discount(&ctx.borrower, &ids, &450_000, &500_000, &7_776_000);
+        assert_eq!(ctx.token.balance(&ctx.borrower) - before, 450_000);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.principal, 450_000);
+        assert_eq!(loan.fixed_repayment, 500_000);
+        // 50K on 450K over 90 days: ~45% simple annual
+        assert_eq!(loan.interest_rate, 4_508);
+        assert_eq!(ctx.client.payoff_amount(&loan_id), 500_000);
+
+        // The same however often accrual is run
+        advance(&ctx, 86_400);
+        ctx.client.accrue_interest(&loan_id);
+        advance(&ctx, 30 * 86_400);
+        assert_eq!(ctx.client.payoff_amount(&loan_id), 500_000);
+        ctx.client.accrue_interest(&loan_id);
+        advance(&ctx, 58 * 86_400);
+        assert_eq!(ctx.client.payoff_amount(&loan_id), 500_000);
+        assert_eq!(ctx.client.get_loan_live(&loan_id, &false).total_owed, 500_000);
+
+        // Past due, liquidation prices off the fixed repayment
+        advance(&ctx, 2 * 86_400);
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(preview.total_debt, 500_000);
+
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan_id, &500_000), 0);
+        assert_eq!(ctx.vault.get_state().total_interest_earned, 50_000);
+    }
+
+    #[test]
+    fn test_prepayment_fee_on_early_payoff() {
+        let ctx = setup();
+        let mut config = default_config();
+        config.prepayment_fee_bps = 200;
+        config.prepayment_free_window_secs = 7 * 86_400;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let r2 = mint_recv(&ctx, 1_000_000, 0);
+        let early = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &7_776_000, &None, &None, &None, &None, &None);
+        let late = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &500_000, &7_776_000, &None, &None, &None, &None, &None);
+
+        // 30 of 90 days in: 2% of 500K scaled by the 60 days left
+        advance(&ctx, 30 * 86_400);
+        let view = ctx.client.get_loan_live(&early, &false);
+        assert_eq!(view.prepayment_fee, 6_666);
+        let payoff = ctx.client.payoff_amount(&early);
+        assert_eq!(payoff, view.total_owed + 6_666);
+
+        // Clearing the balance without the fee isn't a payoff
+        assert_eq!(
+            ctx.client.try_repay_loan(&ctx.borrower, &early, &(payoff - 1)),
+            Err(Ok(Error::RepayTooSmall))
+        );
+        let interest_before = ctx.vault.get_state().total_interest_earned;
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &early, &payoff), 0);
+        assert_eq!(
+            ctx.vault.get_state().total_interest_earned - interest_before,
+            view.projected_interest + 6_666
+        );
+
+        // Within a week of maturity there is no fee
+        advance(&ctx, 55 * 86_400);
+        let view = ctx.client.get_loan_live(&late, &false);
+        assert_eq!(view.prepayment_fee, 0);
+        assert_eq!(ctx.client.payoff_amount(&late), view.total_owed);
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &late, &view.total_owed), 0);
+    }
+
+    #[test]
+    fn test_accrue_interest_emits_accrued_event() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        advance(&ctx, 86_400);
+        let total = ctx.client.accrue_interest(&loan_id);
+        let (_, topics, data) = ctx.env.events().all().last().unwrap();
+        assert_eq!(topics, (symbol_short!("accrued"),).into_val(&ctx.env));
+        let (id, added, new_total, at) = <(u64, i128, i128, u64)>::from_val(&ctx.env, &data);
+        assert_eq!((id, added, new_total, at), (loan_id, total, total, ctx.env.ledger().timestamp()));
+        assert!(added > 0);
+
+        // Nothing accrues at the same timestamp, so nothing is published
+        ctx.client.accrue_interest(&loan_id);
+        assert!(ctx.env.events().all().is_empty());
+
+        advance(&ctx, 86_400);
+        ctx.client.accrue_interest(&loan_id);
+        let (_, _, data) = ctx.env.events().all().last().unwrap();
+        let (_, added2, new_total2, _) = <(u64, i128, i128, u64)>::from_val(&ctx.env, &data);
+        assert_eq!(new_total2, total + added2);
+    }
+
+    #[test]
+    fn test_borrow_lock_repay_unlock_with_owner_consent() {
+        let ctx = setup();
+        ctx.recv.set_lock_consent(&true);
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let r2 = mint_recv(&ctx, 500_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1, r2];
+
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &400_000, &2_592_000, &None, &None, &None, &None, &None);
+        // The borrower's signature on `borrow` covers the locks it triggers
+        let auths = ctx.env.auths();
+        let (_, root) = auths.iter().find(|(addr, _)| *addr == ctx.borrower).unwrap();
+        let lock = Symbol::new(&ctx.env, "lock");
+        assert_eq!(root.sub_invocations.iter().filter(|inv| matches!(&inv.function,
+            AuthorizedFunction::Contract((c, f, _)) if *c == ctx.recv.address && *f == lock)).count(), 2);
+        for rid in ids.iter() {
+            assert_eq!(ctx.recv.get_recv(&rid).status, receivable_token::ReceivableStatus::Collateralized);
+        }
+
+        advance(&ctx, 86_400);
+        let owed = ctx.client.get_loan_live(&loan_id, &false).total_owed;
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan_id, &owed), 0);
+        for rid in ids.iter() {
+            assert_eq!(ctx.recv.get_recv(&rid).status, receivable_token::ReceivableStatus::Active);
+        }
+    }
+
+    #[test]
+    fn test_configure_wires_all_three() {
+        let ctx = setup();
+        assert_eq!(ctx.client.get_contracts(), (ctx.recv.address.clone(), ctx.vault.address.clone()));
+        assert!(ctx.recv.is_borrow(&ctx.client.address));
+        assert_eq!(ctx.vault.get_borrow(), Some(ctx.client.address.clone()));
+
+        // A receivable contract pinned to another currency can't back this vault
+        let other = ctx.env.register_stellar_asset_contract_v2(ctx.admin.clone()).address();
+        let recv = ReceivableTokenContractClient::new(&ctx.env, &ctx.env.register(ReceivableTokenContract, ()));
+        recv.initialize(&ctx.admin, &Address::generate(&ctx.env), &Some(other), &false);
+        assert_eq!(ctx.client.try_configure(&recv.address, &ctx.vault.address), Err(Ok(Error::Miswired)));
+        assert!(!recv.is_borrow(&ctx.client.address));
+        assert_eq!(ctx.client.get_contracts().0, ctx.recv.address);
+    }
+
+    #[test]
+    fn test_end_to_end_mint_borrow_repay_unlock() {
+        let ctx = setup();
+        let rid = mint_recv(&ctx, 1_000_000, 0);
+        let vault_cash = ctx.token.balance(&ctx.vault.address);
+        let wallet = ctx.token.balance(&ctx.borrower);
+
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, rid], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.recv.get_recv(&rid).status, receivable_token::ReceivableStatus::Collateralized);
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet + 500_000);
+        assert_eq!(ctx.token.balance(&ctx.vault.address), vault_cash - 500_000);
+        assert_eq!(ctx.vault.get_state().total_borrowed, 500_000);
+
+        advance(&ctx, 864_000);
+        let owed = ctx.client.get_loan_live(&loan_id, &false).total_owed;
+        let interest = owed - 500_000;
+        assert!(interest > 0);
+        ctx.client.repay_loan(&ctx.borrower, &loan_id, &owed);
+
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Repaid);
+        assert_eq!(ctx.recv.get_recv(&rid).status, receivable_token::ReceivableStatus::Active);
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet - interest);
+        assert_eq!(ctx.token.balance(&ctx.vault.address), vault_cash + interest);
+        let state = ctx.vault.get_state();
+        assert_eq!(state.total_borrowed, 0);
+        assert_eq!(state.total_interest_earned, interest);
+        assert_eq!(state.protocol_reserves, interest * 1000 / 10000);
+        assert_eq!(state.total_deposits, 10_000_000 + interest - state.protocol_reserves);
+    }
+
+    #[test]
+    fn test_liquidation_is_all_or_nothing() {
+        let ctx = setup();
+        let rid = mint_recv(&ctx, 1_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, rid], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 2_592_000 + 1);
+
+        // A failed attempt leaves nothing behind, so an honest one right after isn't held up
+        assert_eq!(ctx.client.try_liquidate(&ctx.liquidator, &loan_id, &None, &Some(1)), Err(Ok(Error::SlippageExceeded)));
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Active);
+        assert_eq!(ctx.recv.get_recv(&rid).status, receivable_token::ReceivableStatus::Collateralized);
+        ctx.client.liquidate(&ctx.liquidator, &loan_id, &None, &None);
+
+        // A successful one closes the loan, so there is nothing left to retry
+        assert_eq!(ctx.client.try_liquidate(&ctx.liquidator, &loan_id, &None, &None), Err(Ok(Error::InvalidStatus)));
+    }
+
+    #[test]
+    fn test_repay_interest_in_fee_token() {
+        let ctx = setup();
+        let gov_admin = ctx.env.register_stellar_asset_contract_v2(ctx.admin.clone()).address();
+        let gov = TokenClient::new(&ctx.env, &gov_admin);
+        StellarAssetClient::new(&ctx.env, &gov_admin).mint(&ctx.borrower, &1_000_000);
+        let treasury = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.vault.try_set_fee_token(&Some(FeeToken { token: gov.address.clone(), rate: 0, recipient: treasury.clone() })),
+            Err(Ok(lending_vault::Error::InvalidFeeToken))
+        );
+        // Two governance tokens per unit of base-asset interest
+        ctx.vault.set_fee_token(&Some(FeeToken { token: gov.address.clone(), rate: 2 * RATE_SCALE, recipient: treasury.clone() }));
+
+        let rid = mint_recv(&ctx, 1_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, rid], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 864_000);
+        let owed = ctx.client.get_loan_live(&loan_id, &false).total_owed;
+        let interest = owed - 500_000;
+        assert!(interest > 0);
+
+        let wallet = ctx.token.balance(&ctx.borrower);
+        let before = ctx.vault.get_state();
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan_id, &owed), 0);
+
+        // Principal in the base asset, interest in the fee token to the treasury
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet - 500_000);
+        assert_eq!(gov.balance(&ctx.borrower), 1_000_000 - 2 * interest);
+        assert_eq!(gov.balance(&treasury), 2 * interest);
+        // LPs and reserves forgo the yield, but the interest is still booked
+        let state = ctx.vault.get_state();
+        assert_eq!(state.total_borrowed, 0);
+        assert_eq!(state.total_deposits, before.total_deposits);
+        assert_eq!(state.protocol_reserves, before.protocol_reserves);
+        assert_eq!(state.total_interest_earned, before.total_interest_earned + interest);
+        assert_eq!(ctx.vault.interest_in_epoch(&ctx.vault.current_epoch()), interest);
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Repaid);
+    }
+
+    #[test]
+    fn test_liquidation_respects_transfer_restriction() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 3_600);
+        let recovery = Address::generate(&ctx.env);
+        ctx.client.set_recovery_address(&recovery);
+        ctx.token_admin.mint(&recovery, &1_000_000);
+
+        // Seized collateral is a transfer too: the recipient must be allowlisted
+        ctx.recv.set_transfer_restricted(&true);
+        assert!(ctx.client.try_force_liquidate(&loan_id, &7).is_err());
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Collateralized);
+
+        ctx.recv.set_allowed(&recovery, &true);
+        ctx.client.force_liquidate(&loan_id, &7);
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Liquidated);
+        assert_eq!(ctx.recv.get_recv(&r1).owner, recovery);
+    }
+
+    #[test]
+    fn test_liquidatable_loans_scan() {
+        let ctx = setup();
+        let day: u64 = 86_400;
+        let open = |face: i128, amount: i128, days: u64| {
+            let rid = mint_recv(&ctx, face, 0);
+            let id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, rid], &amount, &(days * day), &None, &None, &None, &None, &None);
+            (id, rid)
+        };
+        let (short, _) = open(2_000_000, 500_000, 10);
+        let (healthy, _) = open(2_000_000, 500_000, 60);
+        let (repaid, _) = open(2_000_000, 300_000, 10);
+        let (rescored, rid) = open(1_000_000, 650_000, 60);
+        ctx.client.repay_loan(&ctx.borrower, &repaid, &300_000);
+        assert_eq!(ctx.client.liquidatable_loans(&0, &10).len(), 0);
+
+        // Day 15: `short` is past due; `rescored` breaches LTV once its debtor sours
+        advance(&ctx, 15 * day);
+        ctx.recv.update_risk_score(&ctx.verifier, &rid, &10_000);
+        ctx.client.refresh_collateral(&rescored);
+        assert_eq!(ctx.client.liquidatable_loans(&0, &10), soroban_sdk::vec![&ctx.env, short, rescored]);
+        assert_eq!(ctx.client.liquidatable_loans(&healthy, &2).len(), 0);
+        assert_eq!(ctx.client.liquidatable_loans(&healthy, &3), soroban_sdk::vec![&ctx.env, rescored]);
+        assert_eq!(ctx.client.liquidatable_loans(&(rescored + 1), &10).len(), 0);
+
+        // The cap bounds the ids checked, whatever the keeper asks for
+        assert_eq!(ctx.client.liquidatable_loans(&0, &u32::MAX), soroban_sdk::vec![&ctx.env, short, rescored]);
+    }
+
+    #[test]
+    fn test_substitute_collateral() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let r2 = mint_recv(&ctx, 1_000_000, 1000);
+        let r3 = mint_recv(&ctx, 800_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &600_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 3_600);
+
+        assert_eq!(ctx.client.try_substitute_collateral(&ctx.borrower, &loan_id, &r2, &r3), Err(Ok(Error::NotInLoan)));
+        assert_eq!(ctx.client.try_substitute_collateral(&ctx.liquidator, &loan_id, &r1, &r2), Err(Ok(Error::NotBorrower)));
+
+        // 950_000 of collateral still covers 600_000 at 70%
+        assert_eq!(ctx.client.substitute_collateral(&ctx.borrower, &loan_id, &r1, &r2), 950_000);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.receivable_ids, soroban_sdk::vec![&ctx.env, r2]);
+        assert_eq!(loan.collateral_value, 950_000);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Active);
+        assert_eq!(ctx.recv.get_recv(&r2).status, receivable_token::ReceivableStatus::Collateralized);
+        assert_eq!(ctx.recv.locked_by(&r2), Some(ctx.client.address.clone()));
+
+        // 70% of 800_000 is below the debt: rejected, nothing moves
+        assert_eq!(ctx.client.try_substitute_collateral(&ctx.borrower, &loan_id, &r2, &r3), Err(Ok(Error::LTVExceeded)));
+        assert_eq!(ctx.client.get_loan(&loan_id).receivable_ids, soroban_sdk::vec![&ctx.env, r2]);
+        assert_eq!(ctx.recv.get_recv(&r3).status, receivable_token::ReceivableStatus::Active);
+
+        // The incoming receivable has to be free: r2 is already pledged
+        assert_eq!(ctx.client.try_substitute_collateral(&ctx.borrower, &loan_id, &r2, &r2), Err(Ok(Error::RecvNotActive)));
+    }
+
+    #[test]
+    fn test_borrow_against_missing_receivable() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1, 999];
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &ids, &100_000, &2_592_000, &None, &None, &None, &None, &None),
+            Err(Ok(Error::RecvError)),
+        );
+    }
+
+    #[test]
+    fn test_min_loan_duration() {
+        let ctx = setup();
+        let day: u64 = 86_400;
+        let mut config = default_config();
+        config.min_loan_duration = 7 * day;
+        ctx.client.set_config(&config);
+
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let ids = soroban_sdk::vec![&ctx.env, r1];
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &(7 * day - 1), &None, &None, &None, &None, &None),
+            Err(Ok(Error::InvalidDuration))
+        );
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &ids, &500_000, &(config.max_loan_duration + 1), &None, &None, &None, &None, &None),
+            Err(Ok(Error::InvalidDuration))
+        );
+        let loan_id = ctx.client.borrow(&ctx.borrower, &ids, &500_000, &(7 * day), &None, &None, &None, &None, &None);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.due_date, loan.borrowed_at + 7 * day);
+
+        // A floor above the ceiling is rejected
+        config.min_loan_duration = config.max_loan_duration + 1;
+        assert_eq!(ctx.client.try_set_config(&config), Err(Ok(Error::InvalidConfig)));
+    }
+
+    #[test]
+    fn test_debtor_payment_on_pledged_receivable_repays_loan() {
+        let ctx = setup();
+        let day: u64 = 86_400;
+        let debtor = Address::generate(&ctx.env);
+        ctx.token_admin.mint(&debtor, &2_000_000);
+        let r1 = mint_recv(&ctx, 2_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &1_000_000, &(90 * day), &None, &None, &None, &None, &None);
+        advance(&ctx, 30 * day);
+
+        // A partial payment goes to the vault against the loan, not to the borrower
+        let vault_before = ctx.token.balance(&ctx.vault.address);
+        let wallet_before = ctx.token.balance(&ctx.borrower);
+        assert_eq!(ctx.recv.settle_with_payment(&debtor, &r1, &400_000), 400_000);
+        assert_eq!(ctx.token.balance(&ctx.vault.address), vault_before + 400_000);
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet_before);
+        assert_eq!(ctx.token.balance(&ctx.client.address), 0);
+        let interest = 1_000_000 * 1200 * (30 * day) as i128 / (SECONDS_PER_YEAR as i128 * 10000);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!((loan.principal, loan.accrued_interest), (1_000_000 + interest - 400_000, 0));
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Collateralized);
+
+        // The rest pays the loan off; the surplus reaches the borrower and the receivable settles
+        let owed = ctx.client.payoff_amount(&loan_id);
+        assert_eq!(ctx.recv.settle_with_payment(&debtor, &r1, &1_600_000), 2_000_000);
+        assert_eq!(ctx.token.balance(&ctx.vault.address), vault_before + 400_000 + owed);
+        assert_eq!(ctx.token.balance(&ctx.borrower), wallet_before + 1_600_000 - owed);
+        assert_eq!(ctx.token.balance(&ctx.client.address), 0);
+        assert_eq!(ctx.token.balance(&debtor), 0);
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Repaid);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Settled);
+        assert_eq!(ctx.recv.lock_count(&ctx.client.address), 0);
+    }
+
+    #[test]
+    fn test_disputed_collateral_is_worth_nothing() {
+        let ctx = setup();
+        let day: u64 = 86_400;
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let r2 = mint_recv(&ctx, 1_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1, r2], &1_300_000, &(30 * day), &None, &None, &None, &None, &None);
+        let reason = BytesN::from_array(&ctx.env, &[7u8; 32]);
+
+        ctx.recv.raise_dispute(&ctx.verifier, &r1, &reason);
+        assert_eq!(ctx.client.refresh_collateral(&loan_id), 1_000_000);
+        assert!(ctx.client.is_liquidatable(&loan_id));
+        let preview = ctx.client.preview_liquidation(&loan_id);
+        assert_eq!(preview.receivable_values, soroban_sdk::vec![&ctx.env, 0, 1_000_000]);
+        assert_eq!(preview.seized, soroban_sdk::vec![&ctx.env, r2]);
+
+        // Dismissing it restores the pledge and its value
+        ctx.recv.resolve_dispute(&ctx.verifier, &r1, &receivable_token::DisputeOutcome::Dismissed);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Collateralized);
+        assert_eq!(ctx.client.refresh_collateral(&loan_id), 2_000_000);
+
+        // Repaying releases a disputed receivable too; the dispute outlives the loan
+        ctx.recv.raise_dispute(&ctx.verifier, &r1, &reason);
+        ctx.client.repay_loan(&ctx.borrower, &loan_id, &ctx.client.payoff_amount(&loan_id));
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Repaid);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Disputed);
+        assert_eq!(ctx.recv.get_recv(&r2).status, receivable_token::ReceivableStatus::Active);
+        assert_eq!(ctx.recv.lock_count(&ctx.client.address), 0);
+
+        // ...and it can't back a new loan until resolved
+        assert_eq!(
+            ctx.client.try_borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &100_000, &(30 * day), &None, &None, &None, &None, &None),
+            Err(Ok(Error::RecvNotActive))
+        );
+        ctx.recv.resolve_dispute(&ctx.verifier, &r1, &receivable_token::DisputeOutcome::Dismissed);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Active);
+    }
+
+    #[test]
+    fn test_repaid_collateral_goes_to_recipient() {
+        let ctx = setup();
+        let factor = Address::generate(&ctx.env);
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let r2 = mint_recv(&ctx, 2_000_000, 0);
+        let loan1 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &Some(factor.clone()));
+        let loan2 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r2], &500_000, &2_592_000, &None, &None, &None, &None, &Some(factor.clone()));
+        assert_eq!(ctx.client.collateral_recipient(&loan1), Some(factor.clone()));
+
+        // A partial repayment leaves the collateral locked with the borrower
+        ctx.client.repay_loan(&ctx.borrower, &loan1, &200_000);
+        assert_eq!(ctx.recv.get_recv(&r1).owner, ctx.borrower);
+
+        // Repaid in full: unlocked straight into the recipient's hands
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan1, &300_000), 0);
+        let recv = ctx.recv.get_recv(&r1);
+        assert_eq!((recv.owner, recv.status), (factor.clone(), receivable_token::ReceivableStatus::Active));
+        assert_eq!(ctx.recv.get_owner(&factor), soroban_sdk::vec![&ctx.env, r1]);
+        assert!(!ctx.recv.get_owner(&ctx.borrower).contains(r1));
+
+        // The same when a debtor payment clears the loan
+        let debtor = Address::generate(&ctx.env);
+        ctx.token_admin.mint(&debtor, &1_000_000);
+        ctx.recv.settle_with_payment(&debtor, &r2, &1_000_000);
+        assert_eq!(ctx.client.get_loan(&loan2).status, LoanStatus::Repaid);
+        let recv = ctx.recv.get_recv(&r2);
+        assert_eq!((recv.owner, recv.status), (factor.clone(), receivable_token::ReceivableStatus::Active));
+        assert_eq!(ctx.recv.lock_count(&ctx.client.address), 0);
+
+        // Without a recipient the borrower keeps it, as before
+        let r3 = mint_recv(&ctx, 1_000_000, 0);
+        let loan3 = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r3], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        assert_eq!(ctx.client.collateral_recipient(&loan3), None);
+        ctx.client.repay_loan(&ctx.borrower, &loan3, &500_000);
+        assert_eq!(ctx.recv.get_recv(&r3).owner, ctx.borrower);
+    }
+
+    #[test]
+    fn test_auto_defaulted_collateral_revalued_and_released() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let r2 = mint_recv(&ctx, 1_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1, r2], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        let grace = 86_400;
+        ctx.recv.set_default_grace_secs(&grace);
+        ctx.env.ledger().set_timestamp(ctx.recv.get_recv(&r1).maturity_date + grace + 1);
+        let before = ctx.client.get_loan_live(&loan_id, &true).loan.collateral_value;
+        assert!(before > 0);
+
+        // Only r1 is swept; it stops counting as collateral
+        assert_eq!(ctx.recv.mark_defaulted_batch(&soroban_sdk::vec![&ctx.env, r1]), 1);
+        assert_eq!(ctx.client.refresh_collateral(&loan_id), before / 2);
+
+        // The loan still closes, releasing what is left of the basket
+        let owed = ctx.client.payoff_amount(&loan_id);
+        ctx.token_admin.mint(&ctx.borrower, &owed);
+        assert_eq!(ctx.client.repay_loan(&ctx.borrower, &loan_id, &owed), 0);
+        assert_eq!(ctx.recv.get_recv(&r1).status, receivable_token::ReceivableStatus::Defaulted);
+        assert_eq!(ctx.recv.get_recv(&r2).status, receivable_token::ReceivableStatus::Active);
+        assert_eq!(ctx.recv.lock_count(&ctx.client.address), 0);
+    }
+
+    #[test]
+    fn test_accrue_on_read_matches_stored_accrual() {
+        let ctx = setup();
+        let r1 = mint_recv(&ctx, 1_000_000, 500);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &600_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 1_000_000);
+
+        // Off: reads show the stored snapshot
+        assert_eq!(ctx.client.get_loan(&loan_id).accrued_interest, 0);
+        assert_eq!(ctx.client.get_loan_live(&loan_id, &false).loan.accrued_interest, 0);
+
+        let mut config = default_config();
+        config.accrue_on_read = true;
+        ctx.client.set_config(&config);
+        let read = ctx.client.get_loan(&loan_id);
+        let live = ctx.client.get_loan_live(&loan_id, &false);
+        assert!(read.accrued_interest > 0);
+        assert_eq!(live.loan.accrued_interest, read.accrued_interest);
+        assert_eq!(live.projected_interest, read.accrued_interest);
+        assert_eq!(read.last_interest_update, ctx.env.ledger().timestamp());
+
+        // Reading wrote nothing; accruing stores exactly what was shown
+        assert_eq!(ctx.client.accrue_interest(&loan_id), read.accrued_interest);
+        let stored = ctx.client.get_loan(&loan_id);
+        assert_eq!((stored.accrued_interest, stored.last_interest_update), (read.accrued_interest, read.last_interest_update));
+        assert_eq!(ctx.client.get_ltv(&loan_id), live.current_ltv);
+
+        // Closed loans are never projected
+        ctx.token_admin.mint(&ctx.borrower, &stored.accrued_interest);
+        ctx.client.repay_loan(&ctx.borrower, &loan_id, &ctx.client.payoff_amount(&loan_id));
+        advance(&ctx, 1_000);
+        assert_eq!(ctx.client.get_loan(&loan_id).accrued_interest, 0);
+    }
+
+    #[test]
+    fn test_substitution_rechecks_whole_basket() {
+        let ctx = setup();
+        let a1 = mint_recv_owed_by(&ctx, 500_000, 3);
+        let b1 = mint_recv_owed_by(&ctx, 500_000, 2);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, a1, b1], &680_000, &2_592_000, &None, &None, &None, &None, &None);
+
+        // 1.5M at a flat 70% would cover the debt, but the low-factor
+        // currency only backs 350K + 300K
+        let eur = Address::generate(&ctx.env);
+        ctx.client.set_collateral_factor(&eur, &Some(3000));
+        let r_eur = mint_recv_in(&ctx, 1_000_000, &eur);
+        assert_eq!(ctx.client.try_substitute_collateral(&ctx.borrower, &loan_id, &b1, &r_eur), Err(Ok(Error::LTVExceeded)));
+
+        // A swap that concentrates the basket on one debtor is refused as at origination
+        let mut config = default_config();
+        config.max_single_debtor_bps = 5000;
+        ctx.client.set_config(&config);
+        let a2 = mint_recv_owed_by(&ctx, 500_000, 3);
+        assert_eq!(ctx.client.try_substitute_collateral(&ctx.borrower, &loan_id, &b1, &a2), Err(Ok(Error::DebtorConcentration)));
+
+        let b2 = mint_recv_owed_by(&ctx, 500_000, 2);
+        assert_eq!(ctx.client.substitute_collateral(&ctx.borrower, &loan_id, &b1, &b2), 1_000_000);
+        let loan = ctx.client.get_loan(&loan_id);
+        assert_eq!(loan.receivable_ids, soroban_sdk::vec![&ctx.env, a1, b2]);
+        assert_eq!(loan.terms.max_ltv, 7000);
+    }
+
+    #[test]
+    fn test_debtor_payment_with_fee_token_pool() {
+        let ctx = setup();
+        let gov = ctx.env.register_stellar_asset_contract_v2(ctx.admin.clone()).address();
+        let treasury = Address::generate(&ctx.env);
+        ctx.vault.set_fee_token(&Some(FeeToken { token: gov.clone(), rate: 2 * RATE_SCALE, recipient: treasury.clone() }));
+
+        let debtor = Address::generate(&ctx.env);
+        ctx.token_admin.mint(&debtor, &1_000_000);
+        let r1 = mint_recv(&ctx, 1_000_000, 0);
+        let loan_id = ctx.client.borrow(&ctx.borrower, &soroban_sdk::vec![&ctx.env, r1], &500_000, &2_592_000, &None, &None, &None, &None, &None);
+        advance(&ctx, 864_000);
+        let owed = ctx.client.payoff_amount(&loan_id);
+        let interest = owed - 500_000;
+        let before = ctx.vault.get_state();
+
+        // The debtor pays in the base asset; nothing is asked of the fee token
+        assert_eq!(ctx.recv.settle_with_payment(&debtor, &r1, &1_000_000), 1_000_000);
+        assert_eq!(ctx.client.get_loan(&loan_id).status, LoanStatus::Repaid);
+        assert_eq!(TokenClient::new(&ctx.env, &gov).balance(&treasury), 0);
+        assert_eq!(ctx.token.balance(&ctx.client.address), 0);
+
+        // Interest goes through the usual LP / reserve split
+        let reserve = interest * 1000 / 10000;
+        let state = ctx.vault.get_state();
+        assert_eq!(state.total_borrowed, 0);
+        assert_eq!(state.total_deposits, before.total_deposits + interest - reserve);
+        assert_eq!(state.protocol_reserves, before.protocol_reserves + reserve);
+        assert_eq!(state.total_interest_earned, before.total_interest_earned + interest);
+    }
+}
@@ -87,7 +87,8 @@ stellar contract invoke \
     -- \
     initialize \
     --admin $ADMIN \
-    --verifier $VERIFIER
+    --verifier $VERIFIER \
+    --open_mint false
 
 print_info "✓ Receivable Token initialized"
 
@@ -157,7 +158,7 @@ stellar contract invoke \
     --admin $ADMIN \
     --recv_contract $RECV_CONTRACT \
     --vault_contract $VAULT_CONTRACT \
-    --config '{"max_ltv":"7000","liquidation_threshold":"8500","liquidation_penalty":"500","base_interest_rate":"1200","max_loan_duration":7776000,"risk_discount_factor":"5000"}'
+    --config '{"max_ltv":"7000","liquidation_threshold":"8500","liquidation_penalty":"500","base_interest_rate":"1200","max_loan_duration":7776000,"risk_discount_factor":"5000","require_debtor_ack":false,"risk_oracle":null,"liquidation_escalation":"10","max_liquidation_escalation":"1000","max_single_debtor_bps":"0","accrual_after_due":"Continue","late_penalty_rate":"2400","warning_threshold":"8000","late_fee_bps":"0","min_collateral_age_secs":0,"max_staleness_secs":0,"max_interest_only_secs":0,"max_risk_score":10000,"min_repay":"0","min_repay_bps":"0","allow_matured_collateral":false,"matured_haircut_bps":"0","max_single_collateral_bps":"0","prepayment_fee_bps":"0","prepayment_free_window_secs":0,"min_loan_duration":0,"accrue_on_read":false}'
 
 print_info "✓ Borrow Contract initialized (70% LTV, 85% liq threshold, 12% APR)"
 
@@ -167,27 +168,18 @@ print_info "✓ Borrow Contract initialized (70% LTV, 85% liq threshold, 12% APR
 
 print_step 4 "Setting cross-contract authorizations..."
 
-# Receivable Token → authorize borrow contract to lock/unlock (multi-pool safe)
+# Borrow Contract → authorize itself on the receivable token (lock/unlock) and
+# the vault (disburse/repay) in one transaction, checking both point back at it
 stellar contract invoke \
-    --id $RECV_CONTRACT \
-    --source admin \
-    --network $NETWORK \
-    -- \
-    add_borrow \
-    --borrow_contract $BORROW_CONTRACT
-
-print_info "✓ Receivable Token: authorized borrow contract"
-
-# Vault → allow borrow contract to disburse/repay
-stellar contract invoke \
-    --id $VAULT_CONTRACT \
+    --id $BORROW_CONTRACT \
     --source admin \
     --network $NETWORK \
     -- \
-    set_borrow \
-    --borrow_contract $BORROW_CONTRACT
+    configure \
+    --recv_contract $RECV_CONTRACT \
+    --vault_contract $VAULT_CONTRACT
 
-print_info "✓ Lending Vault: authorized borrow contract"
+print_info "✓ Receivable Token + Lending Vault: authorized borrow contract"
 
 # ============================================================
 # Summary
@@ -178,7 +178,7 @@ stellar contract invoke \
     --admin $ADMIN \
     --recv_contract $RECV_CONTRACT \
     --vault_contract $VAULT_CONTRACT \
-    --config "{\"max_ltv\":\"$MAX_LTV\",\"liquidation_threshold\":\"$LIQ_THRESHOLD\",\"liquidation_penalty\":\"$LIQ_PENALTY\",\"base_interest_rate\":\"$INTEREST_RATE\",\"max_loan_duration\":$MAX_DURATION,\"risk_discount_factor\":\"$RISK_DISCOUNT\"}"
+    --config "{\"max_ltv\":\"$MAX_LTV\",\"liquidation_threshold\":\"$LIQ_THRESHOLD\",\"liquidation_penalty\":\"$LIQ_PENALTY\",\"base_interest_rate\":\"$INTEREST_RATE\",\"max_loan_duration\":$MAX_DURATION,\"risk_discount_factor\":\"$RISK_DISCOUNT\",\"require_debtor_ack\":false,\"risk_oracle\":null,\"liquidation_escalation\":\"10\",\"max_liquidation_escalation\":\"1000\",\"max_single_debtor_bps\":\"0\",\"accrual_after_due\":\"Continue\",\"late_penalty_rate\":\"2400\",\"warning_threshold\":\"8000\",\"late_fee_bps\":\"0\",\"min_collateral_age_secs\":0,\"max_staleness_secs\":0,\"max_interest_only_secs\":0,\"max_risk_score\":10000,\"min_repay\":\"0\",\"min_repay_bps\":\"0\",\"allow_matured_collateral\":false,\"matured_haircut_bps\":\"0\",\"max_single_collateral_bps\":\"0\",\"prepayment_fee_bps\":\"0\",\"prepayment_free_window_secs\":0,\"min_loan_duration\":0,\"accrue_on_read\":false}"
 
 print_info "✓ Borrow Contract initialized (${MAX_LTV}bps LTV, ${LIQ_THRESHOLD}bps liq, ${INTEREST_RATE}bps APR)"
 
@@ -188,27 +188,18 @@ print_info "✓ Borrow Contract initialized (${MAX_LTV}bps LTV, ${LIQ_THRESHOLD}
 
 print_step 4 "Setting cross-contract authorizations..."
 
-# Receivable Token → authorize this borrow contract (add_borrow supports multiple)
+# Borrow Contract → authorize itself on the shared receivable token (add_borrow
+# supports multiple pools) and on this pool's vault, atomically
 stellar contract invoke \
-    --id $RECV_CONTRACT \
-    --source admin \
-    --network $NETWORK \
-    -- \
-    add_borrow \
-    --borrow_contract $BORROW_CONTRACT
-
-print_info "✓ Receivable Token: authorized $POOL_NAME borrow contract"
-
-# Vault → allow this borrow contract to disburse/repay
-stellar contract invoke \
-    --id $VAULT_CONTRACT \
+    --id $BORROW_CONTRACT \
     --source admin \
     --network $NETWORK \
     -- \
-    set_borrow \
-    --borrow_contract $BORROW_CONTRACT
+    configure \
+    --recv_contract $RECV_CONTRACT \
+    --vault_contract $VAULT_CONTRACT
 
-print_info "✓ Vault: authorized borrow contract"
+print_info "✓ Receivable Token + Vault: authorized $POOL_NAME borrow contract"
 
 # ============================================================
 # Save pool config
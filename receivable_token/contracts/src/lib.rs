@@ -1,8 +1,10 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, contracterror, symbol_short,
-    Address, BytesN, Env, String, Vec, log,
+    contract, contractimpl, contracttype, contracterror, log, symbol_short,
+    crypto::bls12_381::{Fr, G1Affine, G2Affine},
+    token, xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, String, Symbol, Vec,
 };
 
 // ============================================================================
@@ -17,6 +19,8 @@ pub enum ReceivableStatus {
     Matured,
     Settled,
     Defaulted,
+    Pending,
+    Disputed,
 }
 
 #[contracttype]
@@ -34,6 +38,71 @@ pub struct Receivable {
     pub status: ReceivableStatus,
     pub risk_score: u32,
     pub metadata_uri: String,
+    pub debtor_acknowledged: bool,
+    pub active_from: u64,
+}
+
+/// One verifier re-score of a receivable
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreUpdate {
+    pub old_score: u32,
+    pub new_score: u32,
+    pub timestamp: u64,
+}
+
+/// Running face-value totals by lifecycle bucket. Active covers every live
+/// receivable (`Pending`, `Active`, `Collateralized`, `Matured`, `Disputed`).
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Aggregates {
+    pub total_face_active: i128,
+    pub total_face_settled: i128,
+    pub total_face_defaulted: i128,
+}
+
+/// Issuance fee the creditor pays at `mint`: `flat + face_value * bps / 10000`,
+/// in `token`. Paid straight to `recipient` when set, otherwise held for
+/// `withdraw_mint_fees`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintFee {
+    pub token: Address,
+    pub flat: i128,
+    pub bps: u32,
+    pub recipient: Option<Address>,
+}
+
+/// A Groth16 proof over BLS12-381 for `mint_with_proof`: `proof` is
+/// `A || B || C` (uncompressed G1, G2, G1), and each public input a
+/// big-endian scalar below the curve order
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZkProof {
+    pub proof: Bytes,
+    pub public_inputs: Vec<BytesN<32>>,
+}
+
+/// An open dispute; `prior_status` is what `resolve_dispute` restores
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dispute {
+    pub prior_status: ReceivableStatus,
+    pub reason_hash: BytesN<32>,
+    pub raised_by: Address,
+    pub raised_at: u64,
+}
+
+/// How `resolve_dispute` closes a dispute
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DisputeOutcome {
+    /// The receivable stands as it was
+    Dismissed,
+    /// The debtor won't pay; the receivable is `Defaulted`
+    Defaulted,
+    /// The debtor owes less: the new, lower face value
+    Reduced(i128),
 }
 
 #[contracttype]
@@ -48,6 +117,32 @@ pub enum DataKey {
     TotalActive,
     Paused,
     AuthorizedBorrow(Address),   // per-address flag for multi-pool
+    AckProof(u64),               // debtor acknowledgment proof per receivable
+    BaseCurrency,                // when set, the only currency `mint` accepts
+    OpenMint,                    // testnet mode: creditors mint without the verifier (init-only)
+    Escrow(u64),                 // settlement paid in by the debtor, awaiting the owner's claim
+    Approval(u64),               // (spender, live_until_ledger), temporary; cleared on transfer or leaving Active
+    Operator(Address, Address),  // (owner, operator) -> live_until_ledger, temporary
+    LockConsent,                 // when true, `lock` also needs the receivable owner's auth
+    ScoreHistory(u64),           // Vec<ScoreUpdate>, oldest first, at most MAX_SCORE_HISTORY
+    MetadataFrozen(u64),         // set by `freeze_metadata`; `update_metadata` is then rejected
+    Verifiers,                   // Vec<Address>, persistent; unset = just `Verifier`
+    AmountPaid(u64),             // debtor installments recorded by `record_payment`
+    TransferRestricted,          // when true, recipients must pass `is_allowed`
+    Allowed(Address),            // admin-managed allowlist entry, persistent
+    AllowRegistry,               // external registry answering `is_allowed(addr)`
+    MintFee,                     // MintFee; unset = free issuance
+    MintFeesAccrued(Address),    // fees collected per token, awaiting `withdraw_mint_fees`
+    LockedBy(u64),               // borrow contract holding a Collateralized receivable
+    LockCount(Address),          // receivables a borrow contract currently has locked
+    Aggregates,                  // Aggregates, kept in step with every status change
+    CurrencyFace(Address),       // live face value per currency, persistent
+    Dispute(u64),                // open Dispute, removed on resolution or default
+    MaxFaceValue,                // largest face value `mint` accepts; unset or 0 = unlimited
+    VerifyingKey,                // Groth16 key, persistent; when set, mints need an on-chain verified proof
+    ProofStatement(BytesN<32>),  // hash of public inputs already minted against, persistent
+    DefaultGrace,                // seconds past maturity before `mark_defaulted_batch` may default; unset = off
+    ProofUsed(BytesN<32>),       // zk_proof_hash -> receivable minted with it, persistent, never cleared
 }
 
 #[contracterror]
@@ -65,18 +160,60 @@ pub enum Error {
     NotOwner = 9,
     NotBorrowContract = 10,
     TransferNotAllowed = 11,
+    NotYetActive = 12,
+    CurrencyMismatch = 13,
+    NothingToClaim = 14,
+    InvalidAmount = 15,
+    NotYetMatured = 16,
+    NotApproved = 17,
+    InvalidExpiration = 18,
+    InvalidRiskScore = 19,
+    MetadataFrozen = 20,
+    LastVerifier = 21,
+    RecipientNotAllowed = 22,
+    InvalidMintFee = 23,
+    NotLocker = 24,
+    PoolHasLocks = 25,
+    TooManyIds = 26,
+    FaceValueTooLarge = 27,
+    InvalidVerifyingKey = 28,
+    InvalidProof = 29,
+    ProofRequired = 30,
+    ProofAlreadyUsed = 31,
+    AutoDefaultOff = 32,
+    DuplicateProof = 33,
+    InsufficientFeeBalance = 34,
+    ProofMismatch = 35,
 }
 
+/// Re-scores kept per receivable; older ones are dropped
+pub const MAX_SCORE_HISTORY: u32 = 10;
+
+/// Most ids `get_recvs` reads in one call
+pub const MAX_BATCH_READ: u32 = 50;
+
+const G1_LEN: u32 = 96;
+const G2_LEN: u32 = 192;
+/// `alpha (G1) || beta, gamma, delta (G2)`, followed by one G1 per public input plus one
+const VK_HEADER_LEN: u32 = G1_LEN + 3 * G2_LEN;
+const PROOF_LEN: u32 = 2 * G1_LEN + G2_LEN;
+
 #[contract]
 pub struct ReceivableTokenContract;
 
 #[contractimpl]
 impl ReceivableTokenContract {
 
+    /// `base_currency`, if given, restricts minting to receivables in that
+    /// asset so nothing is issued that the single-asset vault can't lend against.
+    /// `open_mint` drops the verifier signature from `mint` for testnet/demo
+    /// deployments; it can only be chosen here, never switched on later.
     pub fn initialize(
         env: Env,
         admin: Address,
         verifier: Address,
+        base_currency: Option<Address>,
+        open_mint: bool,
     ) -> Result<(), Error> {
         if env.storage().instance().has(&DataKey::Admin) {
             return Err(Error::AlreadyInitialized);
@@ -85,10 +222,15 @@ impl ReceivableTokenContract {
 
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::Verifier, &verifier);
+        env.storage().persistent().set(&DataKey::Verifiers, &soroban_sdk::vec![&env, verifier]);
         env.storage().instance().set(&DataKey::NextId, &1u64);
         env.storage().instance().set(&DataKey::TotalMinted, &0u64);
         env.storage().instance().set(&DataKey::TotalActive, &0u64);
         env.storage().instance().set(&DataKey::Paused, &false);
+        if let Some(currency) = base_currency {
+            env.storage().instance().set(&DataKey::BaseCurrency, &currency);
+        }
+        env.storage().instance().set(&DataKey::OpenMint, &open_mint);
         Ok(())
     }
 
@@ -96,27 +238,249 @@ impl ReceivableTokenContract {
     pub fn add_borrow(env: Env, borrow_contract: Address) -> Result<(), Error> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
-        env.storage().instance().set(&DataKey::AuthorizedBorrow(borrow_contract), &true);
+        env.storage().instance().set(&DataKey::AuthorizedBorrow(borrow_contract.clone()), &true);
+        env.events().publish((symbol_short!("borrow"), borrow_contract), true);
         Ok(())
     }
 
-    /// Revoke a borrow contract's authorization
+    /// Revoke a borrow contract's authorization. Refused while it still holds
+    /// locked collateral, which only it could release.
     pub fn remove_borrow(env: Env, borrow_contract: Address) -> Result<(), Error> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
-        env.storage().instance().remove(&DataKey::AuthorizedBorrow(borrow_contract));
+        if Self::lock_count(env.clone(), borrow_contract.clone()) > 0 { return Err(Error::PoolHasLocks); }
+        env.storage().instance().remove(&DataKey::AuthorizedBorrow(borrow_contract.clone()));
+        env.events().publish((symbol_short!("borrow"), borrow_contract), false);
+        Ok(())
+    }
+
+    pub fn is_borrow(env: Env, borrow_contract: Address) -> bool {
+        env.storage().instance().get(&DataKey::AuthorizedBorrow(borrow_contract)).unwrap_or(false)
+    }
+
+    /// Receivables `borrow_contract` currently holds locked
+    pub fn lock_count(env: Env, borrow_contract: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::LockCount(borrow_contract)).unwrap_or(0)
+    }
+
+    /// Add an attestation provider (or a rotated key) to the verifier set.
+    /// Any member can sign `mint` and the other verifier-gated calls.
+    pub fn add_verifier(env: Env, verifier: Address) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let mut set = Self::get_verifiers(env.clone());
+        if !set.contains(&verifier) {
+            set.push_back(verifier.clone());
+            env.storage().persistent().set(&DataKey::Verifiers, &set);
+        }
+        env.events().publish((symbol_short!("verifier"), verifier), true);
+        Ok(())
+    }
+
+    /// Drop a verifier; the set can't be emptied
+    pub fn remove_verifier(env: Env, verifier: Address) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let mut set = Self::get_verifiers(env.clone());
+        let i = set.first_index_of(&verifier).ok_or(Error::NotVerifier)?;
+        if set.len() == 1 { return Err(Error::LastVerifier); }
+        set.remove(i);
+        env.storage().persistent().set(&DataKey::Verifiers, &set);
+        env.events().publish((symbol_short!("verifier"), verifier), false);
+        Ok(())
+    }
+
+    /// Current verifier set; the address given to `initialize` until changed
+    pub fn get_verifiers(env: Env) -> Vec<Address> {
+        env.storage().persistent().get(&DataKey::Verifiers).unwrap_or_else(|| {
+            let verifier: Address = env.storage().instance().get(&DataKey::Verifier).unwrap();
+            soroban_sdk::vec![&env, verifier]
+        })
+    }
+
+    /// Require the owner's signature, on top of the borrow contract's, for
+    /// `lock`, so a compromised or buggy pool can't pledge receivables the
+    /// owner never offered
+    pub fn set_lock_consent(env: Env, required: bool) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::LockConsent, &required);
+        env.events().publish((symbol_short!("consent"),), required);
+        Ok(())
+    }
+
+    pub fn lock_consent(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::LockConsent).unwrap_or(false)
+    }
+
+    /// Store the Groth16 verifying key `mint_with_proof` checks proofs
+    /// against; from then on `mint` is closed and every receivable needs a
+    /// proof verified on-chain. An empty `vk` removes the key and restores
+    /// the trusted-verifier path
+    pub fn set_verifying_key(env: Env, vk: Bytes) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if vk.is_empty() {
+            env.storage().persistent().remove(&DataKey::VerifyingKey);
+            env.events().publish((symbol_short!("zk_key"),), None::<BytesN<32>>);
+            return Ok(());
+        }
+        if vk.len() < VK_HEADER_LEN + G1_LEN || !(vk.len() - VK_HEADER_LEN).is_multiple_of(G1_LEN) {
+            return Err(Error::InvalidVerifyingKey);
+        }
+        env.storage().persistent().set(&DataKey::VerifyingKey, &vk);
+        let hash: BytesN<32> = env.crypto().sha256(&vk).into();
+        env.events().publish((symbol_short!("zk_key"),), Some(hash));
+        Ok(())
+    }
+
+    pub fn verifying_key(env: Env) -> Option<Bytes> {
+        env.storage().persistent().get(&DataKey::VerifyingKey)
+    }
+
+    /// Grace period after `maturity_date` before anyone may default an
+    /// unpaid receivable through `mark_defaulted_batch`
+    pub fn set_default_grace_secs(env: Env, grace_secs: u64) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::DefaultGrace, &grace_secs);
+        env.events().publish((symbol_short!("grace"),), grace_secs);
+        Ok(())
+    }
+
+    /// `None` while automatic defaults are off
+    pub fn default_grace_secs(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::DefaultGrace)
+    }
+
+    /// Cap the face value of a single mint so one oversized receivable can't
+    /// concentrate risk; larger invoices must be split. 0 removes the cap
+    pub fn set_max_face_value(env: Env, max_face_value: i128) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if max_face_value < 0 {
+            return Err(Error::InvalidFaceValue);
+        }
+        env.storage().instance().set(&DataKey::MaxFaceValue, &max_face_value);
+        env.events().publish((symbol_short!("max_face"),), max_face_value);
+        Ok(())
+    }
+
+    pub fn max_face_value(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::MaxFaceValue).unwrap_or(0)
+    }
+
+    /// Charge creditors an issuance fee at `mint`; `None` makes minting free
+    pub fn set_mint_fee(env: Env, fee: Option<MintFee>) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        match &fee {
+            Some(f) => {
+                if f.flat < 0 || f.bps > 10_000 { return Err(Error::InvalidMintFee); }
+                env.storage().instance().set(&DataKey::MintFee, f);
+            }
+            None => env.storage().instance().remove(&DataKey::MintFee),
+        }
+        env.events().publish((symbol_short!("fee_cfg"),), fee);
+        Ok(())
+    }
+
+    pub fn mint_fee(env: Env) -> Option<MintFee> {
+        env.storage().instance().get(&DataKey::MintFee)
+    }
+
+    /// Fees collected in `token` and not yet withdrawn
+    pub fn mint_fees_accrued(env: Env, token: Address) -> i128 {
+        env.storage().instance().get(&DataKey::MintFeesAccrued(token)).unwrap_or(0)
+    }
+
+    /// Send every fee collected in `token` to `recipient`. Tracked apart from
+    /// settlement escrow, so a fee token that is also a settlement currency
+    /// never touches debtors' funds.
+    pub fn withdraw_mint_fees(env: Env, token: Address, recipient: Address) -> Result<i128, Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let amount = Self::mint_fees_accrued(env.clone(), token.clone());
+        if amount == 0 { return Err(Error::NothingToClaim); }
+        env.storage().instance().remove(&DataKey::MintFeesAccrued(token.clone()));
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &recipient, &amount);
+
+        env.events().publish((symbol_short!("fee_wd"), token), (recipient, amount));
+        Ok(amount)
+    }
+
+    /// Restrict every change of owner (`transfer`, `transfer_from`,
+    /// `transfer_batch`, `force_transfer`, and so liquidation seizures) to
+    /// recipients that pass `is_allowed`. Can be switched on a live contract.
+    pub fn set_transfer_restricted(env: Env, restricted: bool) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::TransferRestricted, &restricted);
+        env.events().publish((symbol_short!("restrict"),), restricted);
+        Ok(())
+    }
+
+    pub fn is_transfer_restricted(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::TransferRestricted).unwrap_or(false)
+    }
+
+    /// Add `account` to (or drop it from) the internal KYC allowlist
+    pub fn set_allowed(env: Env, account: Address, allowed: bool) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let key = DataKey::Allowed(account.clone());
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        env.events().publish((symbol_short!("allowed"), account), allowed);
+        Ok(())
+    }
+
+    /// Also accept recipients an external registry vouches for via
+    /// `is_allowed(addr) -> bool`; `None` removes it
+    pub fn set_allow_registry(env: Env, registry: Option<Address>) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        match &registry {
+            Some(r) => env.storage().instance().set(&DataKey::AllowRegistry, r),
+            None => env.storage().instance().remove(&DataKey::AllowRegistry),
+        }
+        env.events().publish((symbol_short!("registry"),), registry);
         Ok(())
     }
 
+    pub fn allow_registry(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::AllowRegistry)
+    }
+
+    /// On the internal allowlist or approved by the registry
+    pub fn is_allowed(env: Env, account: Address) -> bool {
+        if env.storage().persistent().has(&DataKey::Allowed(account.clone())) { return true; }
+        match Self::allow_registry(env.clone()) {
+            Some(registry) => env.invoke_contract(
+                &registry,
+                &Symbol::new(&env, "is_allowed"),
+                soroban_sdk::vec![&env, account.into_val(&env)],
+            ),
+            None => false,
+        }
+    }
+
     /// Backward-compatible alias — adds the contract to the authorized set
     pub fn set_borrow(env: Env, borrow_contract: Address) -> Result<(), Error> {
         Self::add_borrow(env, borrow_contract)
     }
 
-    /// Mint a tokenized receivable — only callable by the ZK verifier authority
+    /// Mint a tokenized receivable — signed by a member of the verifier set.
+    /// An `active_from` in the future mints it `Pending` until `activate` is called.
+    /// Closed once a verifying key is set; use `mint_with_proof` then.
     pub fn mint(
         env: Env,
         creditor: Address,
+        verifier: Address,
         debtor_hash: BytesN<32>,
         face_value: i128,
         currency: Address,
@@ -124,82 +488,215 @@ impl ReceivableTokenContract {
         zk_proof_hash: BytesN<32>,
         risk_score: u32,
         metadata_uri: String,
+        active_from: u64,
     ) -> Result<u64, Error> {
         Self::require_not_paused(&env)?;
+        if env.storage().persistent().has(&DataKey::VerifyingKey) {
+            return Err(Error::ProofRequired);
+        }
 
-        let verifier: Address = env.storage().instance().get(&DataKey::Verifier).unwrap();
-        verifier.require_auth();
+        if !Self::open_mint(env.clone()) {
+            Self::require_verifier(&env, &verifier)?;
+        }
         creditor.require_auth();
 
-        if face_value <= 0 {
-            return Err(Error::InvalidFaceValue);
-        }
-        if maturity_date <= env.ledger().timestamp() {
-            return Err(Error::InvalidMaturityDate);
+        Self::mint_internal(
+            &env, creditor, debtor_hash, face_value, currency, maturity_date,
+            zk_proof_hash, risk_score, metadata_uri, active_from,
+        )
+    }
+
+    /// Mint against the ZK proof itself rather than its hash. With a
+    /// verifying key set, the proof is checked on-chain and stands in for the
+    /// verifier's signature (`verifier` is ignored), and each set of public
+    /// inputs mints only once. The first two public inputs must carry the
+    /// receivable's commitment (see `proof_commitment`), so a proof only
+    /// mints the receivable it was made for. Without a key this falls back to `mint`'s
+    /// trusted-verifier path. Either way the receivable stores the proof's
+    /// sha256 as `zk_proof_hash`.
+    pub fn mint_with_proof(
+        env: Env,
+        creditor: Address,
+        verifier: Option<Address>,
+        debtor_hash: BytesN<32>,
+        face_value: i128,
+        currency: Address,
+        maturity_date: u64,
+        zk_proof: ZkProof,
+        risk_score: u32,
+        metadata_uri: String,
+        active_from: u64,
+    ) -> Result<u64, Error> {
+        Self::require_not_paused(&env)?;
+
+        match env.storage().persistent().get::<_, Bytes>(&DataKey::VerifyingKey) {
+            Some(vk) => {
+                let (hi, lo) = Self::proof_commitment(&env, &creditor, &debtor_hash, face_value, &currency, maturity_date);
+                let inputs = &zk_proof.public_inputs;
+                if inputs.len() < 2 || inputs.get_unchecked(0) != hi || inputs.get_unchecked(1) != lo {
+                    return Err(Error::ProofMismatch);
+                }
+                Self::verify_proof(&env, &vk, &zk_proof)?;
+                let mut inputs = Bytes::new(&env);
+                for input in zk_proof.public_inputs.iter() {
+                    inputs.append(&input.into());
+                }
+                let statement = DataKey::ProofStatement(env.crypto().sha256(&inputs).into());
+                if env.storage().persistent().has(&statement) { return Err(Error::ProofAlreadyUsed); }
+                env.storage().persistent().set(&statement, &true);
+            }
+            None if !Self::open_mint(env.clone()) => {
+                Self::require_verifier(&env, &verifier.ok_or(Error::NotVerifier)?)?;
+            }
+            None => {}
         }
+        creditor.require_auth();
 
-        let id: u64 = env.storage().instance().get(&DataKey::NextId).unwrap();
-        env.storage().instance().set(&DataKey::NextId, &(id + 1));
+        let zk_proof_hash = env.crypto().sha256(&zk_proof.proof).into();
+        Self::mint_internal(
+            &env, creditor, debtor_hash, face_value, currency, maturity_date,
+            zk_proof_hash, risk_score, metadata_uri, active_from,
+        )
+    }
 
-        let receivable = Receivable {
-            id,
-            owner: creditor.clone(),
-            original_creditor: creditor.clone(),
-            debtor_hash,
-            face_value,
-            currency,
-            issuance_date: env.ledger().timestamp(),
-            maturity_date,
-            zk_proof_hash,
-            status: ReceivableStatus::Active,
-            risk_score,
-            metadata_uri,
-        };
 
-        env.storage().persistent().set(&DataKey::Receivable(id), &receivable);
+    /// Flip a `Pending` receivable to `Active` once `active_from` has passed — permissionless
+    pub fn activate(env: Env, receivable_id: u64) -> Result<(), Error> {
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if recv.status != ReceivableStatus::Pending { return Err(Error::InvalidStatus); }
+        if env.ledger().timestamp() < recv.active_from { return Err(Error::NotYetActive); }
 
-        let mut list: Vec<u64> = env.storage().persistent()
-            .get(&DataKey::OwnerReceivables(creditor.clone()))
-            .unwrap_or(Vec::new(&env));
-        list.push_back(id);
-        env.storage().persistent().set(&DataKey::OwnerReceivables(creditor.clone()), &list);
+        recv.status = ReceivableStatus::Active;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+        env.events().publish((symbol_short!("activate"), receivable_id), recv.active_from);
+        Ok(())
+    }
 
-        let total: u64 = env.storage().instance().get(&DataKey::TotalMinted).unwrap();
-        env.storage().instance().set(&DataKey::TotalMinted, &(total + 1));
-        let active: u64 = env.storage().instance().get(&DataKey::TotalActive).unwrap();
-        env.storage().instance().set(&DataKey::TotalActive, &(active + 1));
+    /// Flip an `Active` receivable to `Matured` once `maturity_date` has passed — permissionless
+    pub fn mature(env: Env, receivable_id: u64) -> Result<(), Error> {
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if recv.status != ReceivableStatus::Active { return Err(Error::InvalidStatus); }
+        if env.ledger().timestamp() < recv.maturity_date { return Err(Error::NotYetMatured); }
+        Self::set_matured(&env, &mut recv);
+        Ok(())
+    }
 
-        env.events().publish((symbol_short!("mint"), creditor), (id, face_value));
-        Ok(id)
+    /// Keeper batch of `mature`: flips every `Active` receivable in the list
+    /// whose `maturity_date` has passed and silently skips the rest.
+    /// `Collateralized` ones are left locked (the borrow contract haircuts
+    /// them by date) and come back `Active` on unlock, to be marked then.
+    /// Returns how many were marked.
+    pub fn mark_matured(env: Env, receivable_ids: Vec<u64>) -> u32 {
+        let now = env.ledger().timestamp();
+        let mut marked = 0;
+        for receivable_id in receivable_ids.iter() {
+            let Ok(mut recv) = Self::get_internal(&env, receivable_id) else { continue };
+            if recv.status != ReceivableStatus::Active || now < recv.maturity_date { continue; }
+            Self::set_matured(&env, &mut recv);
+            marked += 1;
+        }
+        marked
     }
 
-    /// Lock receivable as collateral — only authorized borrow contracts
-    pub fn lock(env: Env, receivable_id: u64, caller: Address) -> Result<(), Error> {
-        Self::require_not_paused(&env)?;
-        Self::verify_authorized_borrow(&env, &caller)?;
+    /// Record the debtor's acknowledgment of the assignment — only callable by the verifier
+    pub fn acknowledge(env: Env, verifier: Address, receivable_id: u64, signature_proof: BytesN<32>) -> Result<(), Error> {
+        Self::require_verifier(&env, &verifier)?;
 
         let mut recv = Self::get_internal(&env, receivable_id)?;
-        if recv.status != ReceivableStatus::Active {
+        if recv.status == ReceivableStatus::Settled || recv.status == ReceivableStatus::Defaulted {
             return Err(Error::InvalidStatus);
         }
-        recv.status = ReceivableStatus::Collateralized;
+        recv.debtor_acknowledged = true;
         env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+        env.storage().persistent().set(&DataKey::AckProof(receivable_id), &signature_proof);
+
+        env.events().publish((symbol_short!("ack"), receivable_id), signature_proof);
         Ok(())
     }
 
-    /// Unlock receivable from collateral — only authorized borrow contracts
-    pub fn unlock(env: Env, receivable_id: u64, caller: Address) -> Result<(), Error> {
+    /// Verifier re-scores a receivable as the debtor's credit changes. Works on
+    /// `Collateralized` receivables (that is when it matters); `Settled` and
+    /// `Defaulted` ones are closed. Each change is kept in `get_score_history`.
+    pub fn update_risk_score(env: Env, verifier: Address, receivable_id: u64, new_score: u32) -> Result<(), Error> {
+        Self::require_verifier(&env, &verifier)?;
+        if new_score > 10000 { return Err(Error::InvalidRiskScore); }
+
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if recv.status == ReceivableStatus::Settled || recv.status == ReceivableStatus::Defaulted {
+            return Err(Error::InvalidStatus);
+        }
+        let old_score = recv.risk_score;
+        recv.risk_score = new_score;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+
+        let mut history = Self::get_score_history(env.clone(), receivable_id);
+        if history.len() >= MAX_SCORE_HISTORY { history.pop_front(); }
+        history.push_back(ScoreUpdate { old_score, new_score, timestamp: env.ledger().timestamp() });
+        env.storage().persistent().set(&DataKey::ScoreHistory(receivable_id), &history);
+
+        env.events().publish((symbol_short!("rescore"), receivable_id), (old_score, new_score));
+        Ok(())
+    }
+
+    /// Owner supersedes the document bundle behind an `Active` receivable
+    /// (corrected invoice, added shipping docs). `content_hash`, if given, is
+    /// only published in the event for off-chain verification.
+    pub fn update_metadata(env: Env, receivable_id: u64, new_uri: String, content_hash: Option<BytesN<32>>) -> Result<(), Error> {
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        recv.owner.require_auth();
+        if recv.status != ReceivableStatus::Active { return Err(Error::InvalidStatus); }
+        if Self::is_metadata_frozen(env.clone(), receivable_id) { return Err(Error::MetadataFrozen); }
+
+        let old_uri = recv.metadata_uri.clone();
+        recv.metadata_uri = new_uri.clone();
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+
+        env.events().publish((symbol_short!("metadata"), receivable_id), (old_uri, new_uri, content_hash));
+        Ok(())
+    }
+
+    /// Verifier pins a receivable's `metadata_uri` for good
+    pub fn freeze_metadata(env: Env, verifier: Address, receivable_id: u64) -> Result<(), Error> {
+        Self::require_verifier(&env, &verifier)?;
+        Self::get_internal(&env, receivable_id)?;
+        env.storage().persistent().set(&DataKey::MetadataFrozen(receivable_id), &true);
+        env.events().publish((symbol_short!("freeze"), receivable_id), verifier);
+        Ok(())
+    }
+
+    pub fn is_metadata_frozen(env: Env, receivable_id: u64) -> bool {
+        env.storage().persistent().get(&DataKey::MetadataFrozen(receivable_id)).unwrap_or(false)
+    }
+
+    /// Lock receivable as collateral — only authorized borrow contracts, which
+    /// pass their own address as `caller`. Matured receivables are accepted
+    /// too; whether they are acceptable collateral is the borrow contract's
+    /// call. With `lock_consent` on, the owner must also authorize. `caller`
+    /// is recorded as the locker: only it can `unlock` or `force_transfer`.
+    pub fn lock(env: Env, receivable_id: u64, caller: Address) -> Result<(), Error> {
+        Self::require_not_paused(&env)?;
         Self::verify_authorized_borrow(&env, &caller)?;
 
         let mut recv = Self::get_internal(&env, receivable_id)?;
-        if recv.status != ReceivableStatus::Collateralized {
+        if recv.status != ReceivableStatus::Active && recv.status != ReceivableStatus::Matured {
             return Err(Error::InvalidStatus);
         }
-        recv.status = ReceivableStatus::Active;
+        if Self::lock_consent(env.clone()) { recv.owner.require_auth(); }
+        recv.status = ReceivableStatus::Collateralized;
+        Self::clear_approval(&env, receivable_id);
         env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+        env.storage().persistent().set(&DataKey::LockedBy(receivable_id), &caller);
+        Self::add_lock_count(&env, &caller, 1);
+        env.events().publish((symbol_short!("lock"), receivable_id), caller);
         Ok(())
     }
 
+    /// Unlock receivable from collateral — only the borrow contract that locked it
+    pub fn unlock(env: Env, receivable_id: u64, caller: Address) -> Result<(), Error> {
+        Self::verify_authorized_borrow(&env, &caller)?;
+        Self::release(&env, receivable_id, &caller)
+    }
+
     /// Transfer receivable ownership (only Active ones)
     pub fn transfer(env: Env, receivable_id: u64, from: Address, to: Address) -> Result<(), Error> {
         Self::require_not_paused(&env)?;
@@ -207,65 +704,468 @@ impl ReceivableTokenContract {
 
         let mut recv = Self::get_internal(&env, receivable_id)?;
         if recv.owner != from { return Err(Error::NotOwner); }
-        if recv.status != ReceivableStatus::Active { return Err(Error::TransferNotAllowed); }
+        if !Self::is_transferable(&recv.status) { return Err(Error::TransferNotAllowed); }
+        Self::require_allowed(&env, &to)?;
 
-        // Update owner lists
-        let mut from_list: Vec<u64> = env.storage().persistent()
+        Self::move_owner(&env, receivable_id, &from, &to);
+        recv.owner = to.clone();
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+        env.events().publish((symbol_short!("transfer"), receivable_id), (from, to, recv.status));
+        Ok(())
+    }
+
+    /// Move several `Active` or `Matured` receivables (e.g. a portfolio sale) in one call.
+    /// Every one must belong to `from`; any failure reverts the whole batch.
+    /// Each owner list is rewritten once rather than per receivable.
+    pub fn transfer_batch(env: Env, receivable_ids: Vec<u64>, from: Address, to: Address) -> Result<(), Error> {
+        Self::require_not_paused(&env)?;
+        from.require_auth();
+        Self::require_allowed(&env, &to)?;
+
+        for (i, receivable_id) in receivable_ids.iter().enumerate() {
+            if receivable_ids.first_index_of(receivable_id) != Some(i as u32) { return Err(Error::TransferNotAllowed); }
+            let mut recv = Self::get_internal(&env, receivable_id)?;
+            if recv.owner != from { return Err(Error::NotOwner); }
+            if !Self::is_transferable(&recv.status) { return Err(Error::TransferNotAllowed); }
+            Self::clear_approval(&env, receivable_id);
+            recv.owner = to.clone();
+            env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+            env.events().publish((symbol_short!("transfer"), receivable_id), (from.clone(), to.clone(), recv.status));
+        }
+
+        let from_list: Vec<u64> = env.storage().persistent()
             .get(&DataKey::OwnerReceivables(from.clone()))
             .unwrap_or(Vec::new(&env));
-        let mut new_from = Vec::new(&env);
+        let mut kept = Vec::new(&env);
         for rid in from_list.iter() {
-            if rid != receivable_id { new_from.push_back(rid); }
+            if !receivable_ids.contains(rid) { kept.push_back(rid); }
         }
-        env.storage().persistent().set(&DataKey::OwnerReceivables(from.clone()), &new_from);
+        env.storage().persistent().set(&DataKey::OwnerReceivables(from.clone()), &kept);
 
         let mut to_list: Vec<u64> = env.storage().persistent()
             .get(&DataKey::OwnerReceivables(to.clone()))
             .unwrap_or(Vec::new(&env));
-        to_list.push_back(receivable_id);
+        to_list.append(&receivable_ids);
         env.storage().persistent().set(&DataKey::OwnerReceivables(to.clone()), &to_list);
-
-        recv.owner = to.clone();
-        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
         Ok(())
     }
 
-    pub fn settle(env: Env, receivable_id: u64) -> Result<(), Error> {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
-        let mut recv = Self::get_internal(&env, receivable_id)?;
-        if recv.status != ReceivableStatus::Active && recv.status != ReceivableStatus::Matured {
-            return Err(Error::InvalidStatus);
+    /// Let `spender` transfer this receivable once, until `live_until_ledger`
+    /// (e.g. a marketplace settling a sale). Replaces any earlier approval;
+    /// a transfer or any status change (maturing included) clears it.
+    pub fn approve(env: Env, owner: Address, spender: Address, receivable_id: u64, live_until_ledger: u32) -> Result<(), Error> {
+        Self::require_not_paused(&env)?;
+        owner.require_auth();
+
+        let recv = Self::get_internal(&env, receivable_id)?;
+        if recv.owner != owner { return Err(Error::NotOwner); }
+        if !Self::is_transferable(&recv.status) { return Err(Error::TransferNotAllowed); }
+        let seq = env.ledger().sequence();
+        if live_until_ledger < seq || live_until_ledger - seq > env.storage().max_ttl() {
+            return Err(Error::InvalidExpiration);
         }
-        recv.status = ReceivableStatus::Settled;
-        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
-        let active: u64 = env.storage().instance().get(&DataKey::TotalActive).unwrap();
-        env.storage().instance().set(&DataKey::TotalActive, &active.saturating_sub(1));
-        Ok(())
-    }
 
-    pub fn mark_default(env: Env, receivable_id: u64) -> Result<(), Error> {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
-        let mut recv = Self::get_internal(&env, receivable_id)?;
-        recv.status = ReceivableStatus::Defaulted;
-        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
-        let active: u64 = env.storage().instance().get(&DataKey::TotalActive).unwrap();
-        env.storage().instance().set(&DataKey::TotalActive, &active.saturating_sub(1));
+        let key = DataKey::Approval(receivable_id);
+        env.storage().temporary().set(&key, &(spender.clone(), live_until_ledger));
+        env.storage().temporary().extend_ttl(&key, live_until_ledger - seq, live_until_ledger - seq);
+
+        env.events().publish((symbol_short!("approve"), receivable_id), (owner, spender, live_until_ledger));
         Ok(())
     }
 
-    // ---- View ----
-    pub fn get_recv(env: Env, receivable_id: u64) -> Result<Receivable, Error> {
-        Self::get_internal(&env, receivable_id)
+    /// The approved spender, if the approval hasn't expired
+    pub fn get_approved(env: Env, receivable_id: u64) -> Option<Address> {
+        let (spender, live_until): (Address, u32) = env.storage().temporary().get(&DataKey::Approval(receivable_id))?;
+        if env.ledger().sequence() > live_until { None } else { Some(spender) }
     }
 
+    /// Let `operator` transfer every transferable receivable `owner` holds, now or
+    /// later, until `live_until_ledger`. `approved = false` revokes at once.
+    /// Operators can only `transfer_from`; they can't lock, settle or
+    /// otherwise change a receivable's status.
+    pub fn set_approval_for_all(env: Env, owner: Address, operator: Address, approved: bool, live_until_ledger: u32) -> Result<(), Error> {
+        Self::require_not_paused(&env)?;
+        owner.require_auth();
+
+        let key = DataKey::Operator(owner.clone(), operator.clone());
+        if approved {
+            let seq = env.ledger().sequence();
+            if live_until_ledger < seq || live_until_ledger - seq > env.storage().max_ttl() {
+                return Err(Error::InvalidExpiration);
+            }
+            env.storage().temporary().set(&key, &live_until_ledger);
+            env.storage().temporary().extend_ttl(&key, live_until_ledger - seq, live_until_ledger - seq);
+        } else {
+            env.storage().temporary().remove(&key);
+        }
+
+        env.events().publish((symbol_short!("operator"), owner), (operator, approved, live_until_ledger));
+        Ok(())
+    }
+
+    pub fn is_approved_for_all(env: Env, owner: Address, operator: Address) -> bool {
+        env.storage().temporary()
+            .get::<_, u32>(&DataKey::Operator(owner, operator))
+            .is_some_and(|live_until| env.ledger().sequence() <= live_until)
+    }
+
+    /// Transfer on the owner's behalf, as the receivable's approved spender
+    /// (consuming the approval) or as one of the owner's operators
+    pub fn transfer_from(env: Env, spender: Address, from: Address, to: Address, receivable_id: u64) -> Result<(), Error> {
+        Self::require_not_paused(&env)?;
+        spender.require_auth();
+
+        if Self::get_approved(env.clone(), receivable_id) != Some(spender.clone())
+            && !Self::is_approved_for_all(env.clone(), from.clone(), spender)
+        {
+            return Err(Error::NotApproved);
+        }
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if recv.owner != from { return Err(Error::NotOwner); }
+        if !Self::is_transferable(&recv.status) { return Err(Error::TransferNotAllowed); }
+        Self::require_allowed(&env, &to)?;
+
+        Self::move_owner(&env, receivable_id, &from, &to);
+        recv.owner = to.clone();
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+        env.events().publish((symbol_short!("transfer"), receivable_id), (from, to, recv.status));
+        Ok(())
+    }
+
+    /// Move a collateralized receivable to a new owner without unlocking it —
+    /// only the borrow contract that locked it (e.g. when a loan is assumed
+    /// or its collateral goes to a designated recipient on repayment)
+    pub fn force_transfer(env: Env, receivable_id: u64, to: Address, caller: Address) -> Result<(), Error> {
+        Self::verify_authorized_borrow(&env, &caller)?;
+        Self::reassign(&env, receivable_id, &to, &caller)
+    }
+
+    pub fn settle(env: Env, receivable_id: u64) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if recv.status != ReceivableStatus::Active && recv.status != ReceivableStatus::Matured {
+            return Err(Error::InvalidStatus);
+        }
+        Self::set_settled(&env, &mut recv);
+        env.events().publish((symbol_short!("settle"), receivable_id), (admin, Self::amount_paid(env.clone(), receivable_id)));
+        Ok(())
+    }
+
+    /// Debtor-side settlement: pull the outstanding balance of the
+    /// receivable's currency from `payer` into escrow and mark it `Settled`.
+    /// `amount` must equal `outstanding`, so a token payment can't close
+    /// someone else's receivable; partial payments go through
+    /// `settle_with_payment`. The owner collects with `claim_settlement`, so a
+    /// payment never depends on pushing to the owner.
+    pub fn deposit_settlement(env: Env, receivable_id: u64, payer: Address, amount: i128) -> Result<(), Error> {
+        Self::require_not_paused(&env)?;
+        payer.require_auth();
+        if amount <= 0 { return Err(Error::InvalidAmount); }
+
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if recv.status != ReceivableStatus::Active && recv.status != ReceivableStatus::Matured {
+            return Err(Error::InvalidStatus);
+        }
+        let paid = Self::amount_paid(env.clone(), receivable_id);
+        if amount != recv.face_value - paid { return Err(Error::InvalidAmount); }
+        token::Client::new(&env, &recv.currency).transfer(&payer, &env.current_contract_address(), &amount);
+
+        env.storage().persistent().set(&DataKey::Escrow(receivable_id), &amount);
+        env.storage().persistent().set(&DataKey::AmountPaid(receivable_id), &recv.face_value);
+        Self::set_settled(&env, &mut recv);
+
+        env.events().publish((symbol_short!("settle"), receivable_id), (payer, amount));
+        Ok(())
+    }
+
+    /// Record an installment the debtor paid off-chain, as observed by the
+    /// admin or a verifier. Once payments reach `face_value` the receivable
+    /// is `Settled`, except while `Collateralized`: it stays locked until the
+    /// loan releases it and can be settled then.
+    pub fn record_payment(env: Env, caller: Address, receivable_id: u64, amount: i128) -> Result<i128, Error> {
+        Self::require_admin_or_verifier(&env, &caller)?;
+        if amount <= 0 { return Err(Error::InvalidAmount); }
+
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if !matches!(recv.status, ReceivableStatus::Active | ReceivableStatus::Matured | ReceivableStatus::Collateralized) {
+            return Err(Error::InvalidStatus);
+        }
+        let paid = Self::amount_paid(env.clone(), receivable_id).checked_add(amount).ok_or(Error::InvalidAmount)?;
+        env.storage().persistent().set(&DataKey::AmountPaid(receivable_id), &paid);
+        env.events().publish((symbol_short!("payment"), receivable_id), (amount, paid, recv.face_value));
+
+        if paid >= recv.face_value && recv.status != ReceivableStatus::Collateralized {
+            Self::set_settled(&env, &mut recv);
+            env.events().publish((symbol_short!("settle"), receivable_id), (caller, paid));
+        }
+        Ok(paid)
+    }
+
+    /// Pay a receivable on-chain: `payer` (the debtor or a servicer) sends
+    /// `amount` of its currency to the owner, counted like `record_payment`;
+    /// reaching `face_value` settles it, and paying past it fails with
+    /// `InvalidAmount`. A `Collateralized` receivable's payment goes to the
+    /// borrow contract that locked it instead, through its
+    /// `on_recv_payment(receivable_id, owner, currency, amount)`, which
+    /// applies it to the loan and returns the receivables to unlock once the
+    /// loan is repaid, with the loan's collateral recipient if it has one;
+    /// they are handed to that recipient before unlocking, and a fully paid
+    /// one is then settled too. Returns the total paid.
+    pub fn settle_with_payment(env: Env, payer: Address, receivable_id: u64, amount: i128) -> Result<i128, Error> {
+        Self::require_not_paused(&env)?;
+        payer.require_auth();
+        if amount <= 0 { return Err(Error::InvalidAmount); }
+
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if !matches!(recv.status, ReceivableStatus::Active | ReceivableStatus::Matured | ReceivableStatus::Collateralized) {
+            return Err(Error::InvalidStatus);
+        }
+        let paid = Self::amount_paid(env.clone(), receivable_id).checked_add(amount).ok_or(Error::InvalidAmount)?;
+        if paid > recv.face_value { return Err(Error::InvalidAmount); }
+        env.storage().persistent().set(&DataKey::AmountPaid(receivable_id), &paid);
+        env.events().publish((symbol_short!("payment"), receivable_id), (amount, paid, recv.face_value));
+
+        let tc = token::Client::new(&env, &recv.currency);
+        if recv.status == ReceivableStatus::Collateralized {
+            let pool = Self::locked_by(env.clone(), receivable_id).ok_or(Error::NotLocker)?;
+            tc.transfer(&payer, &pool, &amount);
+            let (released, recipient): (Vec<u64>, Option<Address>) = env.invoke_contract(
+                &pool,
+                &Symbol::new(&env, "on_recv_payment"),
+                soroban_sdk::vec![
+                    &env,
+                    receivable_id.into_val(&env),
+                    recv.owner.clone().into_val(&env),
+                    recv.currency.clone().into_val(&env),
+                    amount.into_val(&env),
+                ],
+            );
+            for rid in released.iter() {
+                if let Some(to) = &recipient {
+                    Self::reassign(&env, rid, to, &pool)?;
+                }
+                Self::release(&env, rid, &pool)?;
+            }
+            recv = Self::get_internal(&env, receivable_id)?;
+        } else {
+            tc.transfer(&payer, &recv.owner, &amount);
+        }
+
+        if paid == recv.face_value && recv.status != ReceivableStatus::Collateralized {
+            Self::set_settled(&env, &mut recv);
+            env.events().publish((symbol_short!("settle"), receivable_id), (payer, paid));
+        }
+        Ok(paid)
+    }
+
+    pub fn amount_paid(env: Env, receivable_id: u64) -> i128 {
+        env.storage().persistent().get(&DataKey::AmountPaid(receivable_id)).unwrap_or(0)
+    }
+
+    /// Face value not yet paid: `face_value - amount_paid`, and 0 once `Settled`
+    pub fn outstanding(env: Env, receivable_id: u64) -> Result<i128, Error> {
+        let recv = Self::get_internal(&env, receivable_id)?;
+        if recv.status == ReceivableStatus::Settled { return Ok(0); }
+        Ok((recv.face_value - Self::amount_paid(env, receivable_id)).max(0))
+    }
+
+    /// Pay the escrowed settlement out to the receivable's owner. Returns the amount.
+    pub fn claim_settlement(env: Env, receivable_id: u64) -> Result<i128, Error> {
+        let recv = Self::get_internal(&env, receivable_id)?;
+        recv.owner.require_auth();
+
+        let amount: i128 = env.storage().persistent()
+            .get(&DataKey::Escrow(receivable_id))
+            .ok_or(Error::NothingToClaim)?;
+        env.storage().persistent().remove(&DataKey::Escrow(receivable_id));
+        token::Client::new(&env, &recv.currency).transfer(&env.current_contract_address(), &recv.owner, &amount);
+
+        env.events().publish((symbol_short!("claim"), receivable_id), (recv.owner, amount));
+        Ok(amount)
+    }
+
+    /// Borrow contract holding the receivable as collateral, if it is locked.
+    /// `None` for receivables locked before lockers were recorded.
+    pub fn locked_by(env: Env, receivable_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::LockedBy(receivable_id))
+    }
+
+    /// Face value totals: live, settled and defaulted
+    pub fn get_aggregates(env: Env) -> Aggregates {
+        env.storage().instance().get(&DataKey::Aggregates).unwrap_or_default()
+    }
+
+    /// Face value of live receivables in `currency`
+    pub fn get_currency_total(env: Env, currency: Address) -> i128 {
+        env.storage().persistent().get(&DataKey::CurrencyFace(currency)).unwrap_or(0)
+    }
+
+    pub fn escrowed(env: Env, receivable_id: u64) -> i128 {
+        env.storage().persistent().get(&DataKey::Escrow(receivable_id)).unwrap_or(0)
+    }
+
+    pub fn mark_default(env: Env, receivable_id: u64) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if matches!(recv.status, ReceivableStatus::Settled | ReceivableStatus::Defaulted) {
+            return Err(Error::InvalidStatus);
+        }
+        Self::set_defaulted(&env, &mut recv);
+        Ok(())
+    }
+
+    /// Keeper batch of `mark_default`, open to anyone: defaults every
+    /// `Active`, `Matured` or `Collateralized` receivable in the list once
+    /// `default_grace_secs` have passed since its `maturity_date`, and
+    /// silently skips the rest (settled in time, disputed, not yet due). A
+    /// defaulted pledge is released from its lock and borrow contracts value
+    /// it at zero. Returns how many were marked.
+    pub fn mark_defaulted_batch(env: Env, receivable_ids: Vec<u64>) -> Result<u32, Error> {
+        let grace = Self::default_grace_secs(env.clone()).ok_or(Error::AutoDefaultOff)?;
+        let now = env.ledger().timestamp();
+        let mut marked = 0;
+        for receivable_id in receivable_ids.iter() {
+            let Ok(mut recv) = Self::get_internal(&env, receivable_id) else { continue };
+            if !matches!(recv.status, ReceivableStatus::Active | ReceivableStatus::Matured | ReceivableStatus::Collateralized) {
+                continue;
+            }
+            if now <= recv.maturity_date.saturating_add(grace) { continue; }
+            Self::set_defaulted(&env, &mut recv);
+            marked += 1;
+        }
+        Ok(marked)
+    }
+
+    /// Flag a receivable the debtor contests — admin or a verifier. Valid
+    /// from `Active`, `Matured` or `Collateralized`. While `Disputed` it can't
+    /// be locked, transferred or paid, and borrow contracts value it at zero;
+    /// a lock already on it stays until the borrow contract unlocks it.
+    pub fn raise_dispute(env: Env, caller: Address, receivable_id: u64, reason_hash: BytesN<32>) -> Result<(), Error> {
+        Self::require_admin_or_verifier(&env, &caller)?;
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        if !matches!(recv.status, ReceivableStatus::Active | ReceivableStatus::Matured | ReceivableStatus::Collateralized) {
+            return Err(Error::InvalidStatus);
+        }
+        env.storage().persistent().set(&DataKey::Dispute(receivable_id), &Dispute {
+            prior_status: recv.status.clone(),
+            reason_hash: reason_hash.clone(),
+            raised_by: caller.clone(),
+            raised_at: env.ledger().timestamp(),
+        });
+        recv.status = ReceivableStatus::Disputed;
+        Self::clear_approval(&env, receivable_id);
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+        env.events().publish((symbol_short!("dispute"), receivable_id), (caller, reason_hash));
+        Ok(())
+    }
+
+    /// Close a dispute — admin or a verifier. `Dismissed` restores the status
+    /// it had when raised (`Active` if its lock was released meanwhile),
+    /// `Defaulted` defaults it, and `Reduced(face_value)` restores it with a
+    /// lower face value (below the current one and above zero, else
+    /// `InvalidFaceValue`), settling it if `amount_paid` already covers that.
+    pub fn resolve_dispute(env: Env, caller: Address, receivable_id: u64, outcome: DisputeOutcome) -> Result<(), Error> {
+        Self::require_admin_or_verifier(&env, &caller)?;
+        let mut recv = Self::get_internal(&env, receivable_id)?;
+        let dispute: Dispute = env.storage().persistent()
+            .get(&DataKey::Dispute(receivable_id))
+            .ok_or(Error::InvalidStatus)?;
+        if recv.status != ReceivableStatus::Disputed { return Err(Error::InvalidStatus); }
+
+        match outcome.clone() {
+            DisputeOutcome::Dismissed => {
+                recv.status = dispute.prior_status;
+                env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+            }
+            DisputeOutcome::Defaulted => Self::set_defaulted(&env, &mut recv),
+            DisputeOutcome::Reduced(face_value) => {
+                if face_value <= 0 || face_value >= recv.face_value { return Err(Error::InvalidFaceValue); }
+                let cut = recv.face_value - face_value;
+                let mut agg = Self::get_aggregates(env.clone());
+                agg.total_face_active -= cut;
+                env.storage().instance().set(&DataKey::Aggregates, &agg);
+                Self::add_currency_face(&env, &recv.currency, -cut);
+                recv.face_value = face_value;
+                recv.status = dispute.prior_status;
+                env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+                if recv.status != ReceivableStatus::Collateralized && Self::amount_paid(env.clone(), receivable_id) >= face_value {
+                    Self::set_settled(&env, &mut recv);
+                }
+            }
+        }
+        env.storage().persistent().remove(&DataKey::Dispute(receivable_id));
+        env.events().publish((symbol_short!("resolved"), receivable_id), (caller, outcome));
+        Ok(())
+    }
+
+    /// The open dispute on a receivable, if any
+    pub fn get_dispute(env: Env, receivable_id: u64) -> Option<Dispute> {
+        env.storage().persistent().get(&DataKey::Dispute(receivable_id))
+    }
+
+    // ---- View ----
+    pub fn get_recv(env: Env, receivable_id: u64) -> Result<Receivable, Error> {
+        Self::get_internal(&env, receivable_id)
+    }
+
+    /// Several receivables in one call, in input order; `None` where an id
+    /// doesn't exist. At most `MAX_BATCH_READ` ids.
+    pub fn get_recvs(env: Env, receivable_ids: Vec<u64>) -> Result<Vec<Option<Receivable>>, Error> {
+        if receivable_ids.len() > MAX_BATCH_READ { return Err(Error::TooManyIds); }
+        let mut out = Vec::new(&env);
+        for rid in receivable_ids.iter() {
+            out.push_back(env.storage().persistent().get(&DataKey::Receivable(rid)));
+        }
+        Ok(out)
+    }
+
+    /// Verifier re-scores of `receivable_id`, oldest first (last `MAX_SCORE_HISTORY`)
+    pub fn get_score_history(env: Env, receivable_id: u64) -> Vec<ScoreUpdate> {
+        env.storage().persistent()
+            .get(&DataKey::ScoreHistory(receivable_id))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    pub fn get_ack_proof(env: Env, receivable_id: u64) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&DataKey::AckProof(receivable_id))
+    }
+
+    /// Receivables `owner` holds that are still live: settled and defaulted
+    /// ones drop off the list
     pub fn get_owner(env: Env, owner: Address) -> Vec<u64> {
         env.storage().persistent()
             .get(&DataKey::OwnerReceivables(owner))
             .unwrap_or(Vec::new(&env))
     }
 
+    /// Page of `owner`'s receivables in `status`, in the order they were
+    /// acquired; `start` counts matching receivables, not list positions
+    pub fn get_owner_by_status(env: Env, owner: Address, status: ReceivableStatus, start: u32, limit: u32) -> Vec<u64> {
+        let mut out = Vec::new(&env);
+        let matching = Self::get_owner(env.clone(), owner).iter()
+            .filter(|rid| Self::get_internal(&env, *rid).is_ok_and(|r| r.status == status))
+            .skip(start as usize)
+            .take(limit as usize);
+        for rid in matching { out.push_back(rid); }
+        out
+    }
+
+    pub fn open_mint(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::OpenMint).unwrap_or(false)
+    }
+
+    pub fn base_currency(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::BaseCurrency)
+    }
+
+    /// Receivable minted against `zk_proof_hash`, if any. Settled and
+    /// defaulted receivables keep their proof, so it can never back a second mint.
+    pub fn proof_receivable(env: Env, zk_proof_hash: BytesN<32>) -> Option<u64> {
+        env.storage().persistent().get(&DataKey::ProofUsed(zk_proof_hash))
+    }
+
     pub fn total_minted(env: Env) -> u64 {
         env.storage().instance().get(&DataKey::TotalMinted).unwrap_or(0)
     }
@@ -279,6 +1179,7 @@ impl ReceivableTokenContract {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
         env.storage().instance().set(&DataKey::Paused, &true);
+        env.events().publish((symbol_short!("pause"),), true);
         Ok(())
     }
 
@@ -286,174 +1187,1747 @@ impl ReceivableTokenContract {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
         env.storage().instance().set(&DataKey::Paused, &false);
+        env.events().publish((symbol_short!("pause"),), false);
+        Ok(())
+    }
+
+    // ---- Internal ----
+    fn get_internal(env: &Env, id: u64) -> Result<Receivable, Error> {
+        env.storage().persistent().get(&DataKey::Receivable(id)).ok_or(Error::ReceivableNotFound)
+    }
+
+    /// Owners can sell `Active` receivables and overdue `Matured` ones (e.g. to
+    /// a collections agency); anything locked or closed stays put
+    fn is_transferable(status: &ReceivableStatus) -> bool {
+        matches!(status, ReceivableStatus::Active | ReceivableStatus::Matured)
+    }
+
+    fn move_owner(env: &Env, receivable_id: u64, from: &Address, to: &Address) {
+        Self::clear_approval(env, receivable_id);
+
+        // Update owner lists
+        Self::remove_from_owner(env, receivable_id, from);
+        let mut to_list: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::OwnerReceivables(to.clone()))
+            .unwrap_or(Vec::new(env));
+        to_list.push_back(receivable_id);
+        env.storage().persistent().set(&DataKey::OwnerReceivables(to.clone()), &to_list);
+    }
+
+    fn remove_from_owner(env: &Env, receivable_id: u64, owner: &Address) {
+        let list: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::OwnerReceivables(owner.clone()))
+            .unwrap_or(Vec::new(env));
+        let mut kept = Vec::new(env);
+        for rid in list.iter() {
+            if rid != receivable_id { kept.push_back(rid); }
+        }
+        env.storage().persistent().set(&DataKey::OwnerReceivables(owner.clone()), &kept);
+    }
+
+    fn set_defaulted(env: &Env, recv: &mut Receivable) {
+        recv.status = ReceivableStatus::Defaulted;
+        Self::close_face(env, recv);
+        Self::clear_approval(env, recv.id);
+        Self::clear_locker(env, recv.id);
+        Self::remove_from_owner(env, recv.id, &recv.owner);
+        env.storage().persistent().remove(&DataKey::Dispute(recv.id));
+        env.storage().persistent().set(&DataKey::Receivable(recv.id), &*recv);
+        let active: u64 = env.storage().instance().get(&DataKey::TotalActive).unwrap();
+        env.storage().instance().set(&DataKey::TotalActive, &active.saturating_sub(1));
+        env.events().publish((symbol_short!("default"), recv.id), recv.owner.clone());
+    }
+
+    fn set_settled(env: &Env, recv: &mut Receivable) {
+        recv.status = ReceivableStatus::Settled;
+        Self::close_face(env, recv);
+        Self::clear_approval(env, recv.id);
+        Self::remove_from_owner(env, recv.id, &recv.owner);
+        env.storage().persistent().set(&DataKey::Receivable(recv.id), &*recv);
+        let active: u64 = env.storage().instance().get(&DataKey::TotalActive).unwrap();
+        env.storage().instance().set(&DataKey::TotalActive, &active.saturating_sub(1));
+    }
+
+    /// Count a newly minted receivable into the live totals
+    fn open_face(env: &Env, recv: &Receivable) {
+        let mut agg = Self::get_aggregates(env.clone());
+        agg.total_face_active += recv.face_value;
+        env.storage().instance().set(&DataKey::Aggregates, &agg);
+        Self::add_currency_face(env, &recv.currency, recv.face_value);
+    }
+
+    /// Move a receivable that just became `Settled` or `Defaulted` out of the
+    /// live totals and into its closed bucket
+    fn close_face(env: &Env, recv: &Receivable) {
+        let mut agg = Self::get_aggregates(env.clone());
+        agg.total_face_active -= recv.face_value;
+        if recv.status == ReceivableStatus::Settled {
+            agg.total_face_settled += recv.face_value;
+        } else {
+            agg.total_face_defaulted += recv.face_value;
+        }
+        env.storage().instance().set(&DataKey::Aggregates, &agg);
+        Self::add_currency_face(env, &recv.currency, -recv.face_value);
+    }
+
+    fn add_currency_face(env: &Env, currency: &Address, delta: i128) {
+        let key = DataKey::CurrencyFace(currency.clone());
+        let total = Self::get_currency_total(env.clone(), currency.clone()) + delta;
+        if total == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &total);
+        }
+    }
+
+    fn set_matured(env: &Env, recv: &mut Receivable) {
+        recv.status = ReceivableStatus::Matured;
+        Self::clear_approval(env, recv.id);
+        env.storage().persistent().set(&DataKey::Receivable(recv.id), &*recv);
+        env.events().publish((symbol_short!("mature"), recv.id), recv.maturity_date);
+    }
+
+    fn clear_approval(env: &Env, receivable_id: u64) {
+        env.storage().temporary().remove(&DataKey::Approval(receivable_id));
+    }
+
+    fn require_admin_or_verifier(env: &Env, caller: &Address) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        if *caller != admin && !Self::get_verifiers(env.clone()).contains(caller) {
+            return Err(Error::NotAuthorized);
+        }
+        caller.require_auth();
+        Ok(())
+    }
+
+    fn mint_internal(
+        env: &Env,
+        creditor: Address,
+        debtor_hash: BytesN<32>,
+        face_value: i128,
+        currency: Address,
+        maturity_date: u64,
+        zk_proof_hash: BytesN<32>,
+        risk_score: u32,
+        metadata_uri: String,
+        active_from: u64,
+    ) -> Result<u64, Error> {
+        let proof_key = DataKey::ProofUsed(zk_proof_hash.clone());
+        if let Some(existing) = env.storage().persistent().get::<_, u64>(&proof_key) {
+            log!(env, "zk_proof_hash already minted as receivable", existing);
+            return Err(Error::DuplicateProof);
+        }
+        if face_value <= 0 {
+            return Err(Error::InvalidFaceValue);
+        }
+        let max_face_value = Self::max_face_value(env.clone());
+        if max_face_value > 0 && face_value > max_face_value {
+            return Err(Error::FaceValueTooLarge);
+        }
+        if maturity_date <= env.ledger().timestamp() {
+            return Err(Error::InvalidMaturityDate);
+        }
+        if let Some(base) = env.storage().instance().get::<_, Address>(&DataKey::BaseCurrency) {
+            if currency != base {
+                return Err(Error::CurrencyMismatch);
+            }
+        }
+
+        let id: u64 = env.storage().instance().get(&DataKey::NextId).unwrap();
+        let fee = Self::collect_mint_fee(env, &creditor, id, face_value)?;
+        env.storage().instance().set(&DataKey::NextId, &(id + 1));
+
+        let status = if active_from > env.ledger().timestamp() {
+            ReceivableStatus::Pending
+        } else {
+            ReceivableStatus::Active
+        };
+
+        let receivable = Receivable {
+            id,
+            owner: creditor.clone(),
+            original_creditor: creditor.clone(),
+            debtor_hash,
+            face_value,
+            currency,
+            issuance_date: env.ledger().timestamp(),
+            maturity_date,
+            zk_proof_hash,
+            status,
+            risk_score,
+            metadata_uri,
+            debtor_acknowledged: false,
+            active_from,
+        };
+
+        env.storage().persistent().set(&DataKey::Receivable(id), &receivable);
+        env.storage().persistent().set(&proof_key, &id);
+
+        let mut list: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::OwnerReceivables(creditor.clone()))
+            .unwrap_or(Vec::new(env));
+        list.push_back(id);
+        env.storage().persistent().set(&DataKey::OwnerReceivables(creditor.clone()), &list);
+
+        let total: u64 = env.storage().instance().get(&DataKey::TotalMinted).unwrap();
+        env.storage().instance().set(&DataKey::TotalMinted, &(total + 1));
+        let active: u64 = env.storage().instance().get(&DataKey::TotalActive).unwrap();
+        env.storage().instance().set(&DataKey::TotalActive, &(active + 1));
+        Self::open_face(env, &receivable);
+
+        env.events().publish((symbol_short!("mint"), creditor), (id, face_value, fee));
+        Ok(id)
+    }
+
+    /// sha256 over the receivable's fields, split into two field elements:
+    /// the high and low 16 bytes, each left-padded with zeros to stay below the curve order
+    fn proof_commitment(
+        env: &Env, creditor: &Address, debtor_hash: &BytesN<32>, face_value: i128,
+        currency: &Address, maturity_date: u64,
+    ) -> (BytesN<32>, BytesN<32>) {
+        let mut fields = creditor.clone().to_xdr(env);
+        fields.append(&debtor_hash.clone().into());
+        fields.extend_from_array(&face_value.to_be_bytes());
+        fields.append(&currency.clone().to_xdr(env));
+        fields.extend_from_array(&maturity_date.to_be_bytes());
+        let digest = env.crypto().sha256(&fields).to_array();
+
+        let half = |from: usize| {
+            let mut b = [0u8; 32];
+            b[16..].copy_from_slice(&digest[from..from + 16]);
+            BytesN::from_array(env, &b)
+        };
+        (half(0), half(16))
+    }
+
+    /// Groth16 check: `e(A, B) = e(alpha, beta) * e(vk_x, gamma) * e(C, delta)`
+    /// with `vk_x = IC[0] + sum(input[i] * IC[i + 1])`
+    fn verify_proof(env: &Env, vk: &Bytes, zk_proof: &ZkProof) -> Result<(), Error> {
+        let inputs = zk_proof.public_inputs.len();
+        if zk_proof.proof.len() != PROOF_LEN || vk.len() != VK_HEADER_LEN + G1_LEN * (inputs + 1) {
+            return Err(Error::InvalidProof);
+        }
+        let g1 = |b: &Bytes, at: u32| G1Affine::from_bytes(b.slice(at..at + G1_LEN).try_into().unwrap());
+        let g2 = |b: &Bytes, at: u32| G2Affine::from_bytes(b.slice(at..at + G2_LEN).try_into().unwrap());
+
+        let bls = env.crypto().bls12_381();
+        let mut vk_x = g1(vk, VK_HEADER_LEN);
+        for (i, input) in zk_proof.public_inputs.iter().enumerate() {
+            let scalar = Fr::from_bytes(input.clone());
+            // Reject inputs at or above the curve order, which would alias smaller ones
+            if scalar.to_bytes() != input { return Err(Error::InvalidProof); }
+            let ic = g1(vk, VK_HEADER_LEN + G1_LEN * (i as u32 + 1));
+            vk_x = bls.g1_add(&vk_x, &bls.g1_mul(&ic, &scalar));
+        }
+
+        let a = g1(&zk_proof.proof, 0);
+        let b = g2(&zk_proof.proof, G1_LEN);
+        let c = g1(&zk_proof.proof, G1_LEN + G2_LEN);
+        let valid = bls.pairing_check(
+            soroban_sdk::vec![env, -a, g1(vk, 0), vk_x, c],
+            soroban_sdk::vec![env, b, g2(vk, G1_LEN), g2(vk, G1_LEN + G2_LEN), g2(vk, G1_LEN + 2 * G2_LEN)],
+        );
+        if !valid { return Err(Error::InvalidProof); }
         Ok(())
     }
 
-    // ---- Internal ----
-    fn get_internal(env: &Env, id: u64) -> Result<Receivable, Error> {
-        env.storage().persistent().get(&DataKey::Receivable(id)).ok_or(Error::ReceivableNotFound)
+    fn require_verifier(env: &Env, verifier: &Address) -> Result<(), Error> {
+        if !Self::get_verifiers(env.clone()).contains(verifier) { return Err(Error::NotVerifier); }
+        verifier.require_auth();
+        Ok(())
+    }
+
+    /// Pull the issuance fee from `creditor` ahead of any mint writes; returns the amount charged
+    fn collect_mint_fee(env: &Env, creditor: &Address, receivable_id: u64, face_value: i128) -> Result<i128, Error> {
+        let Some(fee) = env.storage().instance().get::<_, MintFee>(&DataKey::MintFee) else { return Ok(0) };
+        let amount = face_value.checked_mul(fee.bps as i128)
+            .and_then(|v| v.checked_add(fee.flat.checked_mul(10_000)?))
+            .ok_or(Error::InvalidFaceValue)? / 10_000;
+        if amount == 0 { return Ok(0); }
+
+        let fee_token = token::Client::new(env, &fee.token);
+        if fee_token.balance(creditor) < amount { return Err(Error::InsufficientFeeBalance); }
+        match &fee.recipient {
+            Some(recipient) => fee_token.transfer(creditor, recipient, &amount),
+            None => {
+                fee_token.transfer(creditor, &env.current_contract_address(), &amount);
+                let key = DataKey::MintFeesAccrued(fee.token.clone());
+                let accrued: i128 = env.storage().instance().get(&key).unwrap_or(0);
+                env.storage().instance().set(&key, &(accrued + amount));
+            }
+        }
+        env.events().publish((symbol_short!("mint_fee"), receivable_id), (fee.token, amount));
+        Ok(amount)
+    }
+
+    fn require_allowed(env: &Env, to: &Address) -> Result<(), Error> {
+        if Self::is_transfer_restricted(env.clone()) && !Self::is_allowed(env.clone(), to.clone()) {
+            return Err(Error::RecipientNotAllowed);
+        }
+        Ok(())
+    }
+
+    fn require_not_paused(env: &Env) -> Result<(), Error> {
+        let paused: bool = env.storage().instance().get(&DataKey::Paused).unwrap_or(false);
+        if paused { Err(Error::ContractPaused) } else { Ok(()) }
+    }
+
+    /// Return a `Collateralized` receivable locked by `caller` to `Active`.
+    /// A `Disputed` one stays disputed but will resolve back to `Active`.
+    fn release(env: &Env, receivable_id: u64, caller: &Address) -> Result<(), Error> {
+        let mut recv = Self::get_internal(env, receivable_id)?;
+        match recv.status {
+            ReceivableStatus::Collateralized => {
+                Self::require_locker(env, receivable_id, caller)?;
+                recv.status = ReceivableStatus::Active;
+                env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+            }
+            ReceivableStatus::Disputed => {
+                let key = DataKey::Dispute(receivable_id);
+                let mut dispute: Dispute = env.storage().persistent().get(&key).ok_or(Error::InvalidStatus)?;
+                if dispute.prior_status != ReceivableStatus::Collateralized { return Err(Error::InvalidStatus); }
+                Self::require_locker(env, receivable_id, caller)?;
+                dispute.prior_status = ReceivableStatus::Active;
+                env.storage().persistent().set(&key, &dispute);
+            }
+            // Defaulting already released the lock; let the loan unwind
+            ReceivableStatus::Defaulted => return Ok(()),
+            _ => return Err(Error::InvalidStatus),
+        }
+        Self::clear_locker(env, receivable_id);
+        env.events().publish((symbol_short!("unlock"), receivable_id), caller.clone());
+        Ok(())
+    }
+
+    /// `force_transfer` once the caller is known to be a borrow contract. A
+    /// receivable disputed while pledged is still locked and moves too.
+    fn reassign(env: &Env, receivable_id: u64, to: &Address, caller: &Address) -> Result<(), Error> {
+        let mut recv = Self::get_internal(env, receivable_id)?;
+        let locked = match recv.status {
+            ReceivableStatus::Collateralized => true,
+            ReceivableStatus::Disputed => env.storage().persistent()
+                .get::<_, Dispute>(&DataKey::Dispute(receivable_id))
+                .is_some_and(|d| d.prior_status == ReceivableStatus::Collateralized),
+            // Dropped from the basket when it defaulted; nothing to move
+            ReceivableStatus::Defaulted => return Ok(()),
+            _ => false,
+        };
+        if !locked { return Err(Error::InvalidStatus); }
+        Self::require_locker(env, receivable_id, caller)?;
+        Self::require_allowed(env, to)?;
+
+        let from = recv.owner.clone();
+        Self::move_owner(env, receivable_id, &from, to);
+        recv.owner = to.clone();
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id), &recv);
+
+        env.events().publish((symbol_short!("fxfer"), receivable_id), (from, to.clone()));
+        Ok(())
+    }
+
+    /// Fail unless `caller` locked the receivable. Receivables locked before
+    /// lockers were recorded stay releasable by any authorized borrow contract.
+    fn require_locker(env: &Env, receivable_id: u64, caller: &Address) -> Result<(), Error> {
+        match env.storage().persistent().get::<_, Address>(&DataKey::LockedBy(receivable_id)) {
+            Some(locker) if locker != *caller => Err(Error::NotLocker),
+            _ => Ok(()),
+        }
+    }
+
+    fn clear_locker(env: &Env, receivable_id: u64) {
+        let key = DataKey::LockedBy(receivable_id);
+        if let Some(locker) = env.storage().persistent().get::<_, Address>(&key) {
+            env.storage().persistent().remove(&key);
+            Self::add_lock_count(env, &locker, -1);
+        }
+    }
+
+    fn add_lock_count(env: &Env, locker: &Address, delta: i32) {
+        let key = DataKey::LockCount(locker.clone());
+        let count: u32 = env.storage().persistent().get(&key).unwrap_or(0);
+        let count = count.saturating_add_signed(delta);
+        if count == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &count);
+        }
+    }
+
+    fn verify_authorized_borrow(env: &Env, caller: &Address) -> Result<(), Error> {
+        caller.require_auth();
+        let authorized: bool = env.storage().instance()
+            .get(&DataKey::AuthorizedBorrow(caller.clone()))
+            .unwrap_or(false);
+        if !authorized {
+            return Err(Error::NotBorrowContract);
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Events, Ledger, LedgerInfo, MockAuth, MockAuthInvoke};
+    use soroban_sdk::FromVal;
+
+    fn setup() -> (Env, ReceivableTokenContractClient<'static>, Address, Address, Address) {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().set(LedgerInfo {
+            timestamp: 1_000_000,
+            protocol_version: 22,
+            sequence_number: 100,
+            network_id: Default::default(),
+            base_reserve: 10,
+            min_temp_entry_ttl: 10,
+            min_persistent_entry_ttl: 10,
+            max_entry_ttl: 3_110_400,
+        });
+
+        let contract_id = env.register(ReceivableTokenContract, ());
+        let client = ReceivableTokenContractClient::new(&env, &contract_id);
+        let admin = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        let creditor = Address::generate(&env);
+
+        client.initialize(&admin, &verifier, &None, &false);
+        // Leak env for static lifetime in tests
+        let client = unsafe { core::mem::transmute::<ReceivableTokenContractClient<'_>, ReceivableTokenContractClient<'static>>(client) };
+        (env, client, admin, verifier, creditor)
+    }
+
+    /// Distinct per mint, since a zk_proof_hash can back only one receivable
+    fn proof_hash(env: &Env, client: &ReceivableTokenContractClient) -> BytesN<32> {
+        let mut hash = [2u8; 32];
+        hash[..8].copy_from_slice(&client.total_minted().to_be_bytes());
+        BytesN::from_array(env, &hash)
+    }
+
+    fn mint_one(env: &Env, client: &ReceivableTokenContractClient, creditor: &Address) -> u64 {
+        let currency = Address::generate(env);
+        client.mint(
+            creditor,
+            &client.get_verifiers().get(0).unwrap(),
+            &BytesN::from_array(env, &[1u8; 32]),
+            &1_000_000_i128,
+            &currency,
+            &2_000_000_u64,
+            &proof_hash(env, client),
+            &500_u32,
+            &String::from_str(env, "ipfs://test"),
+            &0_u64,
+        )
+    }
+
+    #[test]
+    fn test_init_and_mint() {
+        let (env, client, _, _, creditor) = setup();
+        assert_eq!(client.total_minted(), 0);
+
+        let id = mint_one(&env, &client, &creditor);
+        assert_eq!(id, 1);
+        assert_eq!(client.total_minted(), 1);
+        assert_eq!(client.total_active(), 1);
+
+        let recv = client.get_recv(&1);
+        assert_eq!(recv.face_value, 1_000_000);
+        assert_eq!(recv.owner, creditor);
+        assert_eq!(recv.status, ReceivableStatus::Active);
+    }
+
+    #[test]
+    fn test_multiple_mints() {
+        let (env, client, _, _, creditor) = setup();
+        mint_one(&env, &client, &creditor);
+        mint_one(&env, &client, &creditor);
+        assert_eq!(client.total_minted(), 2);
+        assert_eq!(client.get_owner(&creditor).len(), 2);
+    }
+
+    #[test]
+    fn test_lock_unlock() {
+        let (env, client, _, _, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+
+        let id = mint_one(&env, &client, &creditor);
+        client.lock(&id, &borrow_addr);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Collateralized);
+
+        client.unlock(&id, &borrow_addr);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Active);
+    }
+
+    #[test]
+    fn test_transfer() {
+        let (env, client, _, _, creditor) = setup();
+        let buyer = Address::generate(&env);
+        let id = mint_one(&env, &client, &creditor);
+
+        client.transfer(&id, &creditor, &buyer);
+        assert_eq!(client.get_recv(&id).owner, buyer);
+        assert_eq!(client.get_owner(&creditor).len(), 0);
+        assert_eq!(client.get_owner(&buyer).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #11)")]
+    fn test_transfer_collateralized_fails() {
+        let (env, client, _, _, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let id = mint_one(&env, &client, &creditor);
+        client.lock(&id, &borrow_addr);
+        client.transfer(&id, &creditor, &Address::generate(&env));
+    }
+
+    #[test]
+    fn test_settle() {
+        let (env, client, _, _, creditor) = setup();
+        let id = mint_one(&env, &client, &creditor);
+        client.settle(&id);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Settled);
+        assert_eq!(client.total_active(), 0);
+    }
+
+    #[test]
+    fn test_pause_blocks_mint() {
+        let (env, client, _, _, creditor) = setup();
+        client.pause();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mint_one(&env, &client, &creditor);
+        }));
+        assert!(result.is_err());
+
+        client.unpause();
+        let id = mint_one(&env, &client, &creditor);
+        assert_eq!(id, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #6)")]
+    fn test_zero_face_value_fails() {
+        let (env, client, _, verifier, creditor) = setup();
+        let currency = Address::generate(&env);
+        client.mint(
+            &creditor, &verifier, &BytesN::from_array(&env, &[1u8; 32]), &0_i128,
+            &currency, &2_000_000_u64, &proof_hash(&env, &client),
+            &500_u32, &String::from_str(&env, "ipfs://test"), &0_u64,
+        );
+    }
+
+    #[test]
+    fn test_acknowledge() {
+        let (env, client, _, verifier, creditor) = setup();
+        let id = mint_one(&env, &client, &creditor);
+        assert!(!client.get_recv(&id).debtor_acknowledged);
+        assert_eq!(client.get_ack_proof(&id), None);
+
+        let proof = BytesN::from_array(&env, &[7u8; 32]);
+        client.acknowledge(&verifier, &id, &proof);
+        assert!(client.get_recv(&id).debtor_acknowledged);
+        assert_eq!(client.get_ack_proof(&id), Some(proof));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_acknowledge_settled_fails() {
+        let (env, client, _, verifier, creditor) = setup();
+        let id = mint_one(&env, &client, &creditor);
+        client.settle(&id);
+        client.acknowledge(&verifier, &id, &BytesN::from_array(&env, &[7u8; 32]));
+    }
+
+    #[test]
+    fn test_pending_until_activated() {
+        let (env, client, _, verifier, creditor) = setup();
+        let id = client.mint(
+            &creditor, &verifier, &BytesN::from_array(&env, &[1u8; 32]), &1_000_000_i128,
+            &Address::generate(&env), &2_000_000_u64, &proof_hash(&env, &client),
+            &500_u32, &String::from_str(&env, "ipfs://test"), &1_500_000_u64,
+        );
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Pending);
+        assert_eq!(client.total_active(), 1);
+
+        assert_eq!(client.try_activate(&id), Err(Ok(Error::NotYetActive)));
+        assert_eq!(client.try_transfer(&id, &creditor, &Address::generate(&env)), Err(Ok(Error::TransferNotAllowed)));
+
+        env.ledger().set_timestamp(1_500_000);
+        client.activate(&id);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Active);
+        assert_eq!(client.try_activate(&id), Err(Ok(Error::InvalidStatus)));
+    }
+
+    #[test]
+    fn test_force_transfer_keeps_lock() {
+        let (env, client, _, _, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let buyer = Address::generate(&env);
+        let id = mint_one(&env, &client, &creditor);
+
+        // Only locked receivables move this way
+        assert_eq!(client.try_force_transfer(&id, &buyer, &borrow_addr), Err(Ok(Error::InvalidStatus)));
+
+        client.lock(&id, &borrow_addr);
+        client.force_transfer(&id, &buyer, &borrow_addr);
+        let recv = client.get_recv(&id);
+        assert_eq!(recv.owner, buyer);
+        assert_eq!(recv.status, ReceivableStatus::Collateralized);
+        assert_eq!(client.get_owner(&creditor).len(), 0);
+        assert_eq!(client.get_owner(&buyer), soroban_sdk::vec![&env, id]);
+
+        let stranger = Address::generate(&env);
+        assert_eq!(client.try_force_transfer(&id, &creditor, &stranger), Err(Ok(Error::NotBorrowContract)));
+    }
+
+    #[test]
+    fn test_base_currency_enforced_on_mint() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(ReceivableTokenContract, ());
+        let client = ReceivableTokenContractClient::new(&env, &contract_id);
+        let base = Address::generate(&env);
+        let creditor = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.initialize(&Address::generate(&env), &verifier, &Some(base.clone()), &false);
+        assert_eq!(client.base_currency(), Some(base.clone()));
+
+        let mint_in = |currency: &Address| client.try_mint(
+            &creditor,
+            &verifier,
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &1_000_000_i128,
+            currency,
+            &2_000_000_u64,
+            &proof_hash(&env, &client),
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
+        );
+        assert_eq!(mint_in(&Address::generate(&env)), Err(Ok(Error::CurrencyMismatch)));
+        assert_eq!(mint_in(&base), Ok(Ok(1)));
+        assert_eq!(client.total_minted(), 1);
+    }
+
+    #[test]
+    fn test_open_mint_skips_verifier() {
+        // Closed mode: both the verifier and the creditor sign
+        let (env, client, _, verifier, creditor) = setup();
+        assert!(!client.open_mint());
+        mint_one(&env, &client, &creditor);
+        let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+        assert!(signers.contains(&verifier) && signers.contains(&creditor));
+
+        // Open mode: the creditor alone
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(ReceivableTokenContract, ());
+        let client = ReceivableTokenContractClient::new(&env, &contract_id);
+        let verifier = Address::generate(&env);
+        let creditor = Address::generate(&env);
+        client.initialize(&Address::generate(&env), &verifier, &None, &true);
+        assert!(client.open_mint());
+        assert_eq!(mint_one(&env, &client, &creditor), 1);
+        let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+        assert_eq!(signers, std::vec![creditor]);
+    }
+
+    #[test]
+    fn test_settlement_escrow_and_claim() {
+        let (env, client, admin, verifier, creditor) = setup();
+        let sac = env.register_stellar_asset_contract_v2(Address::generate(&env));
+        let usdc = token::Client::new(&env, &sac.address());
+        let debtor = Address::generate(&env);
+        token::StellarAssetClient::new(&env, &sac.address()).mint(&debtor, &1_000_000);
+
+        let id = client.mint(
+            &creditor,
+            &verifier,
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &1_000_000_i128,
+            &sac.address(),
+            &2_000_000_u64,
+            &proof_hash(&env, &client),
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
+        );
+        assert_eq!(client.try_deposit_settlement(&id, &debtor, &0), Err(Ok(Error::InvalidAmount)));
+
+        // An underpayment doesn't settle (or close) the receivable
+        assert_eq!(client.try_deposit_settlement(&id, &debtor, &1), Err(Ok(Error::InvalidAmount)));
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Active);
+        assert_eq!(client.get_owner(&creditor), soroban_sdk::vec![&env, id]);
+
+        // The balance left after off-chain installments is what settles it
+        client.record_payment(&admin, &id, &400_000);
+        assert_eq!(client.try_deposit_settlement(&id, &debtor, &1_000_000), Err(Ok(Error::InvalidAmount)));
+        client.deposit_settlement(&id, &debtor, &600_000);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Settled);
+        assert_eq!(client.amount_paid(&id), 1_000_000);
+        assert_eq!(client.get_aggregates().total_face_settled, 1_000_000);
+        assert_eq!(client.escrowed(&id), 600_000);
+        assert_eq!(usdc.balance(&client.address), 600_000);
+        assert_eq!(client.total_active(), 0);
+        assert_eq!(client.try_deposit_settlement(&id, &debtor, &1), Err(Ok(Error::InvalidStatus)));
+
+        // Only the owner's signature releases the funds
+        let stranger = Address::generate(&env);
+        env.mock_auths(&[MockAuth {
+            address: &stranger,
+            invoke: &MockAuthInvoke {
+                contract: &client.address,
+                fn_name: "claim_settlement",
+                args: (id,).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_claim_settlement(&id).is_err());
+        assert_eq!(client.escrowed(&id), 600_000);
+
+        env.mock_all_auths();
+        assert_eq!(client.claim_settlement(&id), 600_000);
+        assert_eq!(usdc.balance(&creditor), 600_000);
+        assert_eq!(client.escrowed(&id), 0);
+        assert_eq!(client.try_claim_settlement(&id), Err(Ok(Error::NothingToClaim)));
+    }
+
+    #[test]
+    fn test_mature_after_maturity_date() {
+        let (env, client, _, _, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let id = mint_one(&env, &client, &creditor);
+        assert_eq!(client.try_mature(&id), Err(Ok(Error::NotYetMatured)));
+
+        env.ledger().set_timestamp(client.get_recv(&id).maturity_date);
+        client.mature(&id);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Matured);
+        assert_eq!(client.try_mature(&id), Err(Ok(Error::InvalidStatus)));
+
+        // Still lockable as collateral
+        client.lock(&id, &borrow_addr);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Collateralized);
+    }
+
+    #[test]
+    fn test_approve_and_transfer_from() {
+        let (env, client, _, _, creditor) = setup();
+        let spender = Address::generate(&env);
+        let buyer = Address::generate(&env);
+        let id = mint_one(&env, &client, &creditor);
+
+        assert_eq!(client.try_transfer_from(&spender, &creditor, &buyer, &id), Err(Ok(Error::NotApproved)));
+
+        // Expired approvals can't be used
+        client.approve(&creditor, &spender, &id, &104);
+        assert_eq!(client.get_approved(&id), Some(spender.clone()));
+        env.ledger().set_sequence_number(105);
+        assert_eq!(client.get_approved(&id), None);
+        assert_eq!(client.try_transfer_from(&spender, &creditor, &buyer, &id), Err(Ok(Error::NotApproved)));
+
+        client.approve(&creditor, &spender, &id, &108);
+        client.transfer_from(&spender, &creditor, &buyer, &id);
+        assert_eq!(client.get_recv(&id).owner, buyer);
+        assert_eq!(client.get_owner(&buyer), soroban_sdk::vec![&env, id]);
+        assert_eq!(client.get_owner(&creditor).len(), 0);
+        // Consumed by the transfer
+        assert_eq!(client.get_approved(&id), None);
+
+        // A plain transfer also clears an outstanding approval
+        client.approve(&buyer, &spender, &id, &108);
+        client.transfer(&id, &buyer, &creditor);
+        assert_eq!(client.try_transfer_from(&spender, &creditor, &buyer, &id), Err(Ok(Error::NotApproved)));
+
+        // So does leaving Active
+        client.approve(&creditor, &spender, &id, &108);
+        client.settle(&id);
+        assert_eq!(client.get_approved(&id), None);
+    }
+
+    #[test]
+    fn test_operator_transfers_until_revoked() {
+        let (env, client, _, _, creditor) = setup();
+        let custodian = Address::generate(&env);
+        let buyer = Address::generate(&env);
+        let r1 = mint_one(&env, &client, &creditor);
+        let r2 = mint_one(&env, &client, &creditor);
+        let r3 = mint_one(&env, &client, &creditor);
+
+        client.set_approval_for_all(&creditor, &custodian, &true, &108);
+        assert!(client.is_approved_for_all(&creditor, &custodian));
+        client.transfer_from(&custodian, &creditor, &buyer, &r1);
+        client.transfer_from(&custodian, &creditor, &buyer, &r2);
+        assert_eq!(client.get_owner(&buyer), soroban_sdk::vec![&env, r1, r2]);
+
+        // Only over the approving owner's receivables
+        assert_eq!(client.try_transfer_from(&custodian, &buyer, &creditor, &r1), Err(Ok(Error::NotApproved)));
+
+        client.set_approval_for_all(&creditor, &custodian, &false, &0);
+        assert!(!client.is_approved_for_all(&creditor, &custodian));
+        assert_eq!(client.try_transfer_from(&custodian, &creditor, &buyer, &r3), Err(Ok(Error::NotApproved)));
+        assert_eq!(client.get_recv(&r3).owner, creditor);
+    }
+
+    #[test]
+    fn test_lock_consent_requires_owner_auth() {
+        let (env, client, _, _, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        client.set_lock_consent(&true);
+        let id = mint_one(&env, &client, &creditor);
+
+        let lock_invoke = MockAuthInvoke {
+            contract: &client.address,
+            fn_name: "lock",
+            args: (id, borrow_addr.clone()).into_val(&env),
+            sub_invokes: &[],
+        };
+        env.mock_auths(&[MockAuth { address: &borrow_addr, invoke: &lock_invoke }]);
+        assert!(client.try_lock(&id, &borrow_addr).is_err());
+
+        env.mock_auths(&[
+            MockAuth { address: &borrow_addr, invoke: &lock_invoke },
+            MockAuth { address: &creditor, invoke: &lock_invoke },
+        ]);
+        client.lock(&id, &borrow_addr);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Collateralized);
+    }
+
+    #[test]
+    fn test_owner_list_drops_settled_and_filters_by_status() {
+        let (env, client, _, _, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let id1 = mint_one(&env, &client, &creditor);
+        let id2 = mint_one(&env, &client, &creditor);
+        let id3 = mint_one(&env, &client, &creditor);
+
+        client.settle(&id2);
+        client.lock(&id3, &borrow_addr);
+        assert_eq!(client.get_owner(&creditor), soroban_sdk::vec![&env, id1, id3]);
+
+        assert_eq!(client.get_owner_by_status(&creditor, &ReceivableStatus::Active, &0, &10), soroban_sdk::vec![&env, id1]);
+        assert_eq!(client.get_owner_by_status(&creditor, &ReceivableStatus::Collateralized, &0, &10), soroban_sdk::vec![&env, id3]);
+        assert_eq!(client.get_owner_by_status(&creditor, &ReceivableStatus::Settled, &0, &10), soroban_sdk::vec![&env]);
+
+        client.unlock(&id3, &borrow_addr);
+        assert_eq!(client.get_owner_by_status(&creditor, &ReceivableStatus::Active, &1, &1), soroban_sdk::vec![&env, id3]);
+        assert_eq!(client.get_owner_by_status(&creditor, &ReceivableStatus::Active, &0, &1), soroban_sdk::vec![&env, id1]);
+
+        client.mark_default(&id1);
+        assert_eq!(client.get_owner(&creditor), soroban_sdk::vec![&env, id3]);
+    }
+
+    #[test]
+    fn test_update_risk_score_on_collateralized() {
+        let (env, client, _, verifier, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let id = mint_one(&env, &client, &creditor);
+        client.lock(&id, &borrow_addr);
+
+        client.update_risk_score(&verifier, &id, &1200);
+        let (_, topics, data) = env.events().all().last().unwrap();
+        assert_eq!(Symbol::from_val(&env, &topics.get(0).unwrap()), symbol_short!("rescore"));
+        assert_eq!(<(u32, u32)>::from_val(&env, &data), (500, 1200));
+        assert_eq!(client.get_recv(&id).risk_score, 1200);
+
+        env.ledger().with_mut(|l| l.timestamp += 60);
+        client.update_risk_score(&verifier, &id, &900);
+        let history = client.get_score_history(&id);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.get(0).unwrap(), ScoreUpdate { old_score: 500, new_score: 1200, timestamp: env.ledger().timestamp() - 60 });
+        assert_eq!(history.get(1).unwrap(), ScoreUpdate { old_score: 1200, new_score: 900, timestamp: env.ledger().timestamp() });
+
+        assert_eq!(client.try_update_risk_score(&verifier, &id, &10_001), Err(Ok(Error::InvalidRiskScore)));
+
+        // Only the latest MAX_SCORE_HISTORY re-scores are kept
+        for score in 0..MAX_SCORE_HISTORY {
+            client.update_risk_score(&verifier, &id, &score);
+        }
+        let history = client.get_score_history(&id);
+        assert_eq!(history.len(), MAX_SCORE_HISTORY);
+        assert_eq!(history.get(0).unwrap().old_score, 900);
+    }
+
+    #[test]
+    fn test_update_risk_score_rejected_once_settled() {
+        let (env, client, _, verifier, creditor) = setup();
+        let id = mint_one(&env, &client, &creditor);
+        client.settle(&id);
+        assert_eq!(client.try_update_risk_score(&verifier, &id, &100), Err(Ok(Error::InvalidStatus)));
+        assert_eq!(client.get_score_history(&id).len(), 0);
+    }
+
+    #[test]
+    fn test_update_then_freeze_metadata() {
+        let (env, client, _, verifier, creditor) = setup();
+        let id = mint_one(&env, &client, &creditor);
+
+        let uri = String::from_str(&env, "ipfs://corrected");
+        let hash = BytesN::from_array(&env, &[9u8; 32]);
+        client.update_metadata(&id, &uri, &Some(hash.clone()));
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(client.get_recv(&id).metadata_uri, uri);
+        assert_eq!(
+            <(String, String, Option<BytesN<32>>)>::from_val(&env, &data),
+            (String::from_str(&env, "ipfs://test"), uri.clone(), Some(hash)),
+        );
+
+        client.freeze_metadata(&verifier, &id);
+        assert!(client.is_metadata_frozen(&id));
+        assert_eq!(
+            client.try_update_metadata(&id, &String::from_str(&env, "ipfs://late"), &None),
+            Err(Ok(Error::MetadataFrozen))
+        );
+        assert_eq!(client.get_recv(&id).metadata_uri, uri);
+    }
+
+    #[test]
+    fn test_added_verifier_can_mint_until_removed() {
+        let (env, client, _, verifier, creditor) = setup();
+        let second = Address::generate(&env);
+        client.add_verifier(&second);
+        assert_eq!(client.get_verifiers(), soroban_sdk::vec![&env, verifier.clone(), second.clone()]);
+
+        let mint = |signer: &Address| client.try_mint(
+            &creditor, signer, &BytesN::from_array(&env, &[1u8; 32]), &1_000_000_i128,
+            &Address::generate(&env), &2_000_000_u64, &proof_hash(&env, &client),
+            &500_u32, &String::from_str(&env, "ipfs://test"), &0_u64,
+        );
+        assert_eq!(mint(&second), Ok(Ok(1)));
+        let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+        assert!(signers.contains(&second) && !signers.contains(&verifier));
+
+        client.remove_verifier(&second);
+        assert_eq!(mint(&second), Err(Ok(Error::NotVerifier)));
+        assert_eq!(client.try_remove_verifier(&verifier), Err(Ok(Error::LastVerifier)));
+        assert_eq!(client.get_verifiers(), soroban_sdk::vec![&env, verifier]);
+    }
+
+    #[test]
+    fn test_transfer_batch_moves_all_or_nothing() {
+        let (env, client, _, _, creditor) = setup();
+        let buyer = Address::generate(&env);
+        let ids = soroban_sdk::vec![&env,
+            mint_one(&env, &client, &creditor),
+            mint_one(&env, &client, &creditor),
+            mint_one(&env, &client, &creditor),
+        ];
+        let kept = mint_one(&env, &client, &creditor);
+
+        client.transfer_batch(&ids, &creditor, &buyer);
+        assert_eq!(client.get_owner(&buyer), ids);
+        assert_eq!(client.get_owner(&creditor), soroban_sdk::vec![&env, kept]);
+        for id in ids.iter() {
+            assert_eq!(client.get_recv(&id).owner, buyer);
+        }
+
+        // One collateralized receivable sinks the batch
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let other = mint_one(&env, &client, &creditor);
+        client.lock(&other, &borrow_addr);
+        let batch = soroban_sdk::vec![&env, kept, other];
+        assert_eq!(client.try_transfer_batch(&batch, &creditor, &buyer), Err(Ok(Error::TransferNotAllowed)));
+        assert_eq!(client.get_recv(&kept).owner, creditor);
+        assert_eq!(client.get_owner(&creditor), batch);
+
+        assert_eq!(
+            client.try_transfer_batch(&soroban_sdk::vec![&env, kept, kept], &creditor, &buyer),
+            Err(Ok(Error::TransferNotAllowed))
+        );
+    }
+
+    #[test]
+    fn test_mark_matured_batch() {
+        let (env, client, _, verifier, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let due = mint_one(&env, &client, &creditor);
+        let pledged = mint_one(&env, &client, &creditor);
+        let later = client.mint(
+            &creditor, &verifier, &BytesN::from_array(&env, &[1u8; 32]), &1_000_000_i128,
+            &Address::generate(&env), &3_000_000_u64, &proof_hash(&env, &client),
+            &500_u32, &String::from_str(&env, "ipfs://test"), &0_u64,
+        );
+        client.lock(&pledged, &borrow_addr);
+        let batch = soroban_sdk::vec![&env, due, pledged, later, 999];
+        assert_eq!(client.mark_matured(&batch), 0);
+
+        env.ledger().set_timestamp(2_000_000);
+        assert_eq!(client.mark_matured(&batch), 1);
+        let (_, topics, _) = env.events().all().last().unwrap();
+        assert_eq!(Symbol::from_val(&env, &topics.get(0).unwrap()), symbol_short!("mature"));
+        assert_eq!(client.get_recv(&due).status, ReceivableStatus::Matured);
+        assert_eq!(client.get_recv(&pledged).status, ReceivableStatus::Collateralized);
+        assert_eq!(client.get_recv(&later).status, ReceivableStatus::Active);
+        assert_eq!(client.total_active(), 3);
+
+        // Released collateral is marked on the next pass
+        client.unlock(&pledged, &borrow_addr);
+        assert_eq!(client.mark_matured(&batch), 1);
+        assert_eq!(client.get_recv(&pledged).status, ReceivableStatus::Matured);
     }
 
-    fn require_not_paused(env: &Env) -> Result<(), Error> {
-        let paused: bool = env.storage().instance().get(&DataKey::Paused).unwrap_or(false);
-        if paused { Err(Error::ContractPaused) } else { Ok(()) }
+    #[test]
+    fn test_record_payments_until_settled() {
+        let (env, client, admin, verifier, creditor) = setup();
+        let id = mint_one(&env, &client, &creditor);
+
+        assert_eq!(client.try_record_payment(&creditor, &id, &100), Err(Ok(Error::NotAuthorized)));
+        assert_eq!(client.record_payment(&verifier, &id, &300_000), 300_000);
+        assert_eq!(client.record_payment(&admin, &id, &300_000), 600_000);
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(<(i128, i128, i128)>::from_val(&env, &data), (300_000, 600_000, 1_000_000));
+        assert_eq!(client.outstanding(&id), 400_000);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Active);
+
+        assert_eq!(client.record_payment(&verifier, &id, &400_000), 1_000_000);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Settled);
+        assert_eq!(client.outstanding(&id), 0);
+        assert_eq!(client.total_active(), 0);
+        assert_eq!(client.try_record_payment(&verifier, &id, &1), Err(Ok(Error::InvalidStatus)));
     }
 
-    fn verify_authorized_borrow(env: &Env, caller: &Address) -> Result<(), Error> {
-        caller.require_auth();
-        let authorized: bool = env.storage().instance()
-            .get(&DataKey::AuthorizedBorrow(caller.clone()))
-            .unwrap_or(false);
-        if !authorized {
-            return Err(Error::NotBorrowContract);
+    #[contract]
+    struct MockRegistry;
+
+    #[contractimpl]
+    impl MockRegistry {
+        pub fn approve(env: Env, account: Address) {
+            env.storage().instance().set(&account, &true);
+        }
+
+        pub fn is_allowed(env: Env, account: Address) -> bool {
+            env.storage().instance().has(&account)
         }
-        Ok(())
     }
-}
 
-// ============================================================================
-// Tests
-// ============================================================================
-#[cfg(test)]
-mod test {
-    extern crate std;
-    use super::*;
-    use soroban_sdk::testutils::{Address as _, Ledger, LedgerInfo};
+    #[test]
+    fn test_transfer_restriction_allowlist_and_registry() {
+        let (env, client, _, _, creditor) = setup();
+        let buyer = Address::generate(&env);
+        let id = mint_one(&env, &client, &creditor);
 
-    fn setup() -> (Env, ReceivableTokenContractClient<'static>, Address, Address, Address) {
-        let env = Env::default();
-        env.mock_all_auths();
-        env.ledger().set(LedgerInfo {
-            timestamp: 1_000_000,
-            protocol_version: 21,
-            sequence_number: 100,
-            network_id: Default::default(),
-            base_reserve: 10,
-            min_temp_entry_ttl: 10,
-            min_persistent_entry_ttl: 10,
-            max_entry_ttl: 3_110_400,
-        });
+        // Off by default; switch it on for a live contract
+        client.transfer(&id, &creditor, &buyer);
+        client.set_transfer_restricted(&true);
+        assert_eq!(client.try_transfer(&id, &buyer, &creditor), Err(Ok(Error::RecipientNotAllowed)));
 
-        let contract_id = env.register_contract(None, ReceivableTokenContract);
-        let client = ReceivableTokenContractClient::new(&env, &contract_id);
-        let admin = Address::generate(&env);
-        let verifier = Address::generate(&env);
-        let creditor = Address::generate(&env);
+        // Internal list
+        client.set_allowed(&creditor, &true);
+        client.transfer(&id, &buyer, &creditor);
+        assert_eq!(client.get_recv(&id).owner, creditor);
+        let batch = soroban_sdk::vec![&env, id];
+        assert_eq!(client.try_transfer_batch(&batch, &creditor, &buyer), Err(Ok(Error::RecipientNotAllowed)));
 
-        client.initialize(&admin, &verifier);
-        // Leak env for static lifetime in tests
-        let client = unsafe { core::mem::transmute(client) };
-        (env, client, admin, verifier, creditor)
-    }
+        // External registry
+        let registry = MockRegistryClient::new(&env, &env.register(MockRegistry, ()));
+        client.set_allow_registry(&Some(registry.address.clone()));
+        assert!(!client.is_allowed(&buyer));
+        registry.approve(&buyer);
+        assert!(client.is_allowed(&buyer));
+        client.transfer_batch(&batch, &creditor, &buyer);
+        assert_eq!(client.get_recv(&id).owner, buyer);
 
-    fn mint_one(env: &Env, client: &ReceivableTokenContractClient, creditor: &Address) -> u64 {
-        let currency = Address::generate(env);
-        client.mint(
-            creditor,
-            &BytesN::from_array(env, &[1u8; 32]),
-            &1_000_000_i128,
-            &currency,
-            &2_000_000_u64,
-            &BytesN::from_array(env, &[2u8; 32]),
-            &500_u32,
-            &String::from_str(env, "ipfs://test"),
-        )
+        client.set_allowed(&creditor, &false);
+        assert_eq!(client.try_transfer(&id, &buyer, &creditor), Err(Ok(Error::RecipientNotAllowed)));
     }
 
     #[test]
-    fn test_init_and_mint() {
+    fn test_transfer_matured_receivable() {
         let (env, client, _, _, creditor) = setup();
-        assert_eq!(client.total_minted(), 0);
-
+        let agency = Address::generate(&env);
         let id = mint_one(&env, &client, &creditor);
-        assert_eq!(id, 1);
-        assert_eq!(client.total_minted(), 1);
-        assert_eq!(client.total_active(), 1);
+        let maturity = client.get_recv(&id).maturity_date;
+        env.ledger().set_timestamp(maturity);
+        client.mature(&id);
 
-        let recv = client.get_recv(&1);
-        assert_eq!(recv.face_value, 1_000_000);
-        assert_eq!(recv.owner, creditor);
-        assert_eq!(recv.status, ReceivableStatus::Active);
+        client.transfer(&id, &creditor, &agency);
+        let (_, topics, data) = env.events().all().last().unwrap();
+        assert_eq!(Symbol::from_val(&env, &topics.get(0).unwrap()), Symbol::new(&env, "transfer"));
+        assert_eq!(
+            <(Address, Address, ReceivableStatus)>::from_val(&env, &data),
+            (creditor.clone(), agency.clone(), ReceivableStatus::Matured),
+        );
+        let recv = client.get_recv(&id);
+        assert_eq!((recv.owner, recv.status), (agency.clone(), ReceivableStatus::Matured));
+        assert_eq!(client.get_owner(&creditor).len(), 0);
+        assert_eq!(client.get_owner(&agency), soroban_sdk::vec![&env, id]);
+
+        // Locked collateral is still off the market
+        let borrow = Address::generate(&env);
+        client.add_borrow(&borrow);
+        client.lock(&id, &borrow);
+        assert_eq!(client.try_transfer(&id, &agency, &creditor), Err(Ok(Error::TransferNotAllowed)));
     }
 
     #[test]
-    fn test_multiple_mints() {
+    fn test_mint_fee_collected_and_withdrawn() {
         let (env, client, _, _, creditor) = setup();
+        let sac = env.register_stellar_asset_contract_v2(Address::generate(&env));
+        let fee_token = token::Client::new(&env, &sac.address());
+        token::StellarAssetClient::new(&env, &sac.address()).mint(&creditor, &100_000);
+
+        // Free by default
         mint_one(&env, &client, &creditor);
+        assert_eq!(fee_token.balance(&creditor), 100_000);
+
+        let bad = MintFee { token: sac.address(), flat: 0, bps: 10_001, recipient: None };
+        assert_eq!(client.try_set_mint_fee(&Some(bad)), Err(Ok(Error::InvalidMintFee)));
+        client.set_mint_fee(&Some(MintFee { token: sac.address(), flat: 500, bps: 10, recipient: None }));
+
+        // 500 flat + 0.1% of 1_000_000
+        let id = mint_one(&env, &client, &creditor);
+        let (_, topics, data) = env.events().all().get(env.events().all().len() - 2).unwrap();
+        assert_eq!(Symbol::from_val(&env, &topics.get(0).unwrap()), Symbol::new(&env, "mint_fee"));
+        assert_eq!(u64::from_val(&env, &topics.get(1).unwrap()), id);
+        assert_eq!(<(Address, i128)>::from_val(&env, &data), (sac.address(), 1_500));
         mint_one(&env, &client, &creditor);
-        assert_eq!(client.total_minted(), 2);
-        assert_eq!(client.get_owner(&creditor).len(), 2);
+        assert_eq!(fee_token.balance(&creditor), 97_000);
+        assert_eq!(client.mint_fees_accrued(&sac.address()), 3_000);
+
+        let treasury = Address::generate(&env);
+        assert_eq!(client.withdraw_mint_fees(&sac.address(), &treasury), 3_000);
+        assert_eq!(fee_token.balance(&treasury), 3_000);
+        assert_eq!(client.mint_fees_accrued(&sac.address()), 0);
+        assert_eq!(client.try_withdraw_mint_fees(&sac.address(), &treasury), Err(Ok(Error::NothingToClaim)));
+
+        client.set_mint_fee(&None);
+        mint_one(&env, &client, &creditor);
+        assert_eq!(fee_token.balance(&creditor), 97_000);
     }
 
     #[test]
-    fn test_lock_unlock() {
+    fn test_lifecycle_events() {
         let (env, client, admin, _, creditor) = setup();
-        let borrow_addr = Address::generate(&env);
-        client.set_borrow(&borrow_addr);
+        let borrow = Address::generate(&env);
+        let buyer = Address::generate(&env);
+        let last = || {
+            let (_, topics, data) = env.events().all().last().unwrap();
+            (Symbol::from_val(&env, &topics.get(0).unwrap()), topics, data)
+        };
+
+        client.add_borrow(&borrow);
+        let (name, topics, data) = last();
+        assert_eq!(name, Symbol::new(&env, "borrow"));
+        assert_eq!(Address::from_val(&env, &topics.get(1).unwrap()), borrow);
+        assert!(bool::from_val(&env, &data));
 
         let id = mint_one(&env, &client, &creditor);
-        client.lock(&id, &borrow_addr);
-        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Collateralized);
+        let (name, topics, data) = last();
+        assert_eq!(name, Symbol::new(&env, "mint"));
+        assert_eq!(Address::from_val(&env, &topics.get(1).unwrap()), creditor);
+        assert_eq!(<(u64, i128, i128)>::from_val(&env, &data), (id, 1_000_000, 0));
 
-        client.unlock(&id, &borrow_addr);
-        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Active);
+        client.lock(&id, &borrow);
+        let (name, topics, data) = last();
+        assert_eq!(name, Symbol::new(&env, "lock"));
+        assert_eq!(u64::from_val(&env, &topics.get(1).unwrap()), id);
+        assert_eq!(Address::from_val(&env, &data), borrow);
+
+        client.unlock(&id, &borrow);
+        let (name, topics, data) = last();
+        assert_eq!(name, Symbol::new(&env, "unlock"));
+        assert_eq!(u64::from_val(&env, &topics.get(1).unwrap()), id);
+        assert_eq!(Address::from_val(&env, &data), borrow);
+
+        client.transfer(&id, &creditor, &buyer);
+        let (name, _, data) = last();
+        assert_eq!(name, Symbol::new(&env, "transfer"));
+        assert_eq!(
+            <(Address, Address, ReceivableStatus)>::from_val(&env, &data),
+            (creditor.clone(), buyer.clone(), ReceivableStatus::Active),
+        );
+
+        client.settle(&id);
+        let (name, topics, data) = last();
+        assert_eq!(name, Symbol::new(&env, "settle"));
+        assert_eq!(u64::from_val(&env, &topics.get(1).unwrap()), id);
+        assert_eq!(<(Address, i128)>::from_val(&env, &data), (admin.clone(), 0));
+
+        let other = mint_one(&env, &client, &creditor);
+        client.mark_default(&other);
+        let (name, topics, data) = last();
+        assert_eq!(name, Symbol::new(&env, "default"));
+        assert_eq!(u64::from_val(&env, &topics.get(1).unwrap()), other);
+        assert_eq!(Address::from_val(&env, &data), creditor);
+
+        client.pause();
+        let (name, _, data) = last();
+        assert_eq!(name, Symbol::new(&env, "pause"));
+        assert!(bool::from_val(&env, &data));
+    }
+
+    /// Stands in for a borrow contract: calls `lock`/`unlock` the way the
+    /// real one does, passing its own address
+    #[contract]
+    struct MockPool;
+
+    #[contractimpl]
+    impl MockPool {
+        pub fn pledge(env: Env, recv_contract: Address, receivable_id: u64) {
+            let args = soroban_sdk::vec![&env, receivable_id.into_val(&env), env.current_contract_address().into_val(&env)];
+            env.invoke_contract::<()>(&recv_contract, &Symbol::new(&env, "lock"), args);
+        }
+
+        pub fn release(env: Env, recv_contract: Address, receivable_id: u64) {
+            let args = soroban_sdk::vec![&env, receivable_id.into_val(&env), env.current_contract_address().into_val(&env)];
+            env.invoke_contract::<()>(&recv_contract, &Symbol::new(&env, "unlock"), args);
+        }
     }
 
     #[test]
-    fn test_transfer() {
+    fn test_only_locker_can_release() {
         let (env, client, _, _, creditor) = setup();
-        let buyer = Address::generate(&env);
+        let pool_a = MockPoolClient::new(&env, &env.register(MockPool, ()));
+        let pool_b = MockPoolClient::new(&env, &env.register(MockPool, ()));
+        client.add_borrow(&pool_a.address);
+        client.add_borrow(&pool_b.address);
         let id = mint_one(&env, &client, &creditor);
 
-        client.transfer(&id, &creditor, &buyer);
-        assert_eq!(client.get_recv(&id).owner, buyer);
-        assert_eq!(client.get_owner(&creditor).len(), 0);
-        assert_eq!(client.get_owner(&buyer).len(), 1);
+        pool_a.pledge(&client.address, &id);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Collateralized);
+        assert_eq!(client.locked_by(&id), Some(pool_a.address.clone()));
+
+        // Another authorized pool can't free or move pool A's collateral
+        assert!(pool_b.try_release(&client.address, &id).is_err());
+        assert_eq!(client.try_unlock(&id, &pool_b.address), Err(Ok(Error::NotLocker)));
+        assert_eq!(
+            client.try_force_transfer(&id, &Address::generate(&env), &pool_b.address),
+            Err(Ok(Error::NotLocker)),
+        );
+
+        pool_a.release(&client.address, &id);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Active);
+        assert_eq!(client.locked_by(&id), None);
+
+        // Once released, either pool may pledge it
+        pool_b.pledge(&client.address, &id);
+        assert_eq!(client.locked_by(&id), Some(pool_b.address.clone()));
     }
 
     #[test]
-    #[should_panic(expected = "Error(Contract, #11)")]
-    fn test_transfer_collateralized_fails() {
+    fn test_pools_hold_their_own_locks() {
         let (env, client, _, _, creditor) = setup();
-        let borrow_addr = Address::generate(&env);
-        client.set_borrow(&borrow_addr);
-        let id = mint_one(&env, &client, &creditor);
-        client.lock(&id, &borrow_addr);
-        client.transfer(&id, &creditor, &Address::generate(&env));
+        let pool_a = MockPoolClient::new(&env, &env.register(MockPool, ()));
+        let pool_b = MockPoolClient::new(&env, &env.register(MockPool, ()));
+        client.add_borrow(&pool_a.address);
+        client.add_borrow(&pool_b.address);
+        let for_a = mint_one(&env, &client, &creditor);
+        let for_b = mint_one(&env, &client, &creditor);
+
+        pool_a.pledge(&client.address, &for_a);
+        pool_b.pledge(&client.address, &for_b);
+        assert_eq!(client.lock_count(&pool_a.address), 1);
+        assert_eq!(client.lock_count(&pool_b.address), 1);
+
+        // Neither can release the other's collateral
+        assert!(pool_a.try_release(&client.address, &for_b).is_err());
+        assert!(pool_b.try_release(&client.address, &for_a).is_err());
+
+        // A pool holding locks can't be removed until it has released them
+        assert_eq!(client.try_remove_borrow(&pool_a.address), Err(Ok(Error::PoolHasLocks)));
+        pool_a.release(&client.address, &for_a);
+        assert_eq!(client.lock_count(&pool_a.address), 0);
+        client.remove_borrow(&pool_a.address);
+        assert!(!client.is_borrow(&pool_a.address));
+        assert!(pool_a.try_pledge(&client.address, &for_a).is_err());
+
+        // A default releases the lock too
+        client.mark_default(&for_b);
+        assert_eq!(client.lock_count(&pool_b.address), 0);
+        client.remove_borrow(&pool_b.address);
     }
 
     #[test]
-    fn test_settle() {
+    fn test_get_recvs_batch() {
         let (env, client, _, _, creditor) = setup();
-        let id = mint_one(&env, &client, &creditor);
-        client.settle(&id);
+        let a = mint_one(&env, &client, &creditor);
+        let b = mint_one(&env, &client, &creditor);
+
+        let got = client.get_recvs(&soroban_sdk::vec![&env, b, 99, a, b]);
+        assert_eq!(got.len(), 4);
+        assert_eq!(got.get(0).unwrap().unwrap().id, b);
+        assert!(got.get(1).unwrap().is_none());
+        assert_eq!(got.get(2).unwrap().unwrap().id, a);
+        assert_eq!(got.get(3).unwrap().unwrap().id, b);
+        assert_eq!(client.get_recvs(&Vec::new(&env)).len(), 0);
+
+        let mut too_many = Vec::new(&env);
+        for i in 0..=MAX_BATCH_READ { too_many.push_back(i as u64); }
+        assert_eq!(client.try_get_recvs(&too_many), Err(Ok(Error::TooManyIds)));
+    }
+
+    #[test]
+    fn test_aggregates_reconcile_with_brute_force() {
+        let (env, client, admin, verifier, creditor) = setup();
+        let sac = env.register_stellar_asset_contract_v2(Address::generate(&env));
+        let debtor = Address::generate(&env);
+        token::StellarAssetClient::new(&env, &sac.address()).mint(&debtor, &i128::MAX);
+        let borrow = Address::generate(&env);
+        client.add_borrow(&borrow);
+        let currencies = [sac.address(), Address::generate(&env)];
+
+        let reconcile = || {
+            let mut expect = Aggregates::default();
+            let mut per_currency = [0i128; 2];
+            let mut ids = Vec::new(&env);
+            for id in 1..=client.total_minted() { ids.push_back(id); }
+            for recv in client.get_recvs(&ids).iter() {
+                let recv = recv.unwrap();
+                match recv.status {
+                    ReceivableStatus::Settled => expect.total_face_settled += recv.face_value,
+                    ReceivableStatus::Defaulted => expect.total_face_defaulted += recv.face_value,
+                    _ => {
+                        expect.total_face_active += recv.face_value;
+                        let i = currencies.iter().position(|c| *c == recv.currency).unwrap();
+                        per_currency[i] += recv.face_value;
+                    }
+                }
+            }
+            assert_eq!(client.get_aggregates(), expect);
+            for (i, currency) in currencies.iter().enumerate() {
+                assert_eq!(client.get_currency_total(currency), per_currency[i]);
+            }
+        };
+
+        // Deterministic pseudo-random walk over every transition path
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |n: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % n
+        };
+        for _ in 0..40 {
+            let minted = client.total_minted();
+            let id = if minted == 0 { 0 } else { 1 + next(minted) };
+            match next(8) {
+                0 | 1 => {
+                    let currency = &currencies[next(2) as usize];
+                    let face = 1_000 * (1 + next(50) as i128);
+                    let active_from = if next(4) == 0 { 1_500_000 } else { 0 };
+                    client.mint(
+                        &creditor, &verifier, &BytesN::from_array(&env, &[1u8; 32]), &face,
+                        currency, &2_000_000_u64, &proof_hash(&env, &client),
+                        &500_u32, &String::from_str(&env, "ipfs://test"), &active_from,
+                    );
+                }
+                2 => { let _ = client.try_settle(&id); }
+                3 => { let _ = client.try_mark_default(&id); }
+                4 => { let _ = client.try_record_payment(&admin, &id, &(1_000 * (1 + next(30) as i128))); }
+                5 => {
+                    let owed = client.try_outstanding(&id).ok().and_then(Result::ok).unwrap_or(1_000);
+                    let _ = client.try_deposit_settlement(&id, &debtor, &owed);
+                }
+                6 => {
+                    if client.try_lock(&id, &borrow).is_err() { let _ = client.try_unlock(&id, &borrow); }
+                }
+                _ => { let _ = client.try_mark_matured(&soroban_sdk::vec![&env, id]); }
+            }
+            reconcile();
+        }
+        let agg = client.get_aggregates();
+        assert!(agg.total_face_active > 0 && agg.total_face_settled > 0 && agg.total_face_defaulted > 0);
+    }
+
+    #[test]
+    fn test_settle_with_payment_pays_owner() {
+        let (env, client, _, verifier, creditor) = setup();
+        let sac = env.register_stellar_asset_contract_v2(Address::generate(&env));
+        let usdc = token::Client::new(&env, &sac.address());
+        let debtor = Address::generate(&env);
+        token::StellarAssetClient::new(&env, &sac.address()).mint(&debtor, &1_500_000);
+
+        let id = client.mint(
+            &creditor,
+            &verifier,
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &1_000_000_i128,
+            &sac.address(),
+            &2_000_000_u64,
+            &proof_hash(&env, &client),
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
+        );
+        let buyer = Address::generate(&env);
+        client.transfer(&id, &creditor, &buyer);
+
+        // Installments go straight to the current owner
+        assert_eq!(client.settle_with_payment(&debtor, &id, &400_000), 400_000);
+        assert_eq!(usdc.balance(&buyer), 400_000);
+        assert_eq!(client.outstanding(&id), 600_000);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Active);
+        assert_eq!(client.try_settle_with_payment(&debtor, &id, &600_001), Err(Ok(Error::InvalidAmount)));
+
+        assert_eq!(client.settle_with_payment(&debtor, &id, &600_000), 1_000_000);
+        let event = env.events().all().last().unwrap();
+        assert_eq!(usdc.balance(&buyer), 1_000_000);
+        assert_eq!(usdc.balance(&debtor), 500_000);
+        assert_eq!(usdc.balance(&client.address), 0);
         assert_eq!(client.get_recv(&id).status, ReceivableStatus::Settled);
+        assert_eq!(client.get_aggregates().total_face_settled, 1_000_000);
+        assert_eq!(<(Address, i128)>::from_val(&env, &event.2), (debtor.clone(), 1_000_000));
+        assert_eq!(client.try_settle_with_payment(&debtor, &id, &1), Err(Ok(Error::InvalidStatus)));
+    }
+
+    #[test]
+    fn test_dispute_and_resolution() {
+        let (env, client, admin, verifier, creditor) = setup();
+        let id = mint_one(&env, &client, &creditor);
+        let borrow = Address::generate(&env);
+        client.add_borrow(&borrow);
+        let reason = BytesN::from_array(&env, &[7u8; 32]);
+
+        assert_eq!(client.try_raise_dispute(&creditor, &id, &reason), Err(Ok(Error::NotAuthorized)));
+        assert_eq!(client.try_resolve_dispute(&verifier, &id, &DisputeOutcome::Dismissed), Err(Ok(Error::InvalidStatus)));
+        client.raise_dispute(&verifier, &id, &reason);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Disputed);
+        assert_eq!(client.get_dispute(&id).unwrap().prior_status, ReceivableStatus::Active);
+
+        // Contested receivables can't move, be pledged or be paid
+        let buyer = Address::generate(&env);
+        assert_eq!(client.try_transfer(&id, &creditor, &buyer), Err(Ok(Error::TransferNotAllowed)));
+        assert_eq!(client.try_lock(&id, &borrow), Err(Ok(Error::InvalidStatus)));
+        assert_eq!(client.try_record_payment(&verifier, &id, &1), Err(Ok(Error::InvalidStatus)));
+        assert_eq!(client.try_raise_dispute(&verifier, &id, &reason), Err(Ok(Error::InvalidStatus)));
+
+        // A partial win for the debtor cuts the face value and restores the status
+        assert_eq!(
+            client.try_resolve_dispute(&admin, &id, &DisputeOutcome::Reduced(1_000_000)),
+            Err(Ok(Error::InvalidFaceValue))
+        );
+        client.resolve_dispute(&admin, &id, &DisputeOutcome::Reduced(400_000));
+        let recv = client.get_recv(&id);
+        assert_eq!((recv.status, recv.face_value), (ReceivableStatus::Active, 400_000));
+        assert_eq!(client.get_aggregates().total_face_active, 400_000);
+        assert_eq!(client.get_currency_total(&recv.currency), 400_000);
+        assert_eq!(client.get_dispute(&id), None);
+
+        // Disputing a pledged one keeps the lock until the pool lets go
+        client.lock(&id, &borrow);
+        client.raise_dispute(&verifier, &id, &reason);
+        client.unlock(&id, &borrow);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Disputed);
+        assert_eq!(client.lock_count(&borrow), 0);
+        assert_eq!(client.get_dispute(&id).unwrap().prior_status, ReceivableStatus::Active);
+
+        client.resolve_dispute(&verifier, &id, &DisputeOutcome::Defaulted);
+        assert_eq!(client.get_recv(&id).status, ReceivableStatus::Defaulted);
+        assert_eq!(client.get_aggregates().total_face_defaulted, 400_000);
         assert_eq!(client.total_active(), 0);
+        assert_eq!(client.get_dispute(&id), None);
     }
 
     #[test]
-    fn test_pause_blocks_mint() {
+    fn test_max_face_value_caps_mint() {
+        let (env, client, _, verifier, creditor) = setup();
+        assert_eq!(client.max_face_value(), 0);
+        let mint_face = |face: i128| client.try_mint(
+            &creditor,
+            &verifier,
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &face,
+            &Address::generate(&env),
+            &2_000_000_u64,
+            &proof_hash(&env, &client),
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
+        );
+        assert!(mint_face(50_000_000).is_ok());
+
+        assert_eq!(client.try_set_max_face_value(&-1), Err(Ok(Error::InvalidFaceValue)));
+        client.set_max_face_value(&10_000_000);
+        assert_eq!(client.max_face_value(), 10_000_000);
+        assert_eq!(mint_face(10_000_001), Err(Ok(Error::FaceValueTooLarge)));
+        assert_eq!(mint_face(10_000_000), Ok(Ok(2)));
+        assert_eq!(client.get_recv(&2).face_value, 10_000_000);
+
+        // 0 lifts the cap again
+        client.set_max_face_value(&0);
+        assert_eq!(mint_face(10_000_001), Ok(Ok(3)));
+    }
+
+    // Groth16 fixtures over BLS12-381 for a circuit proving knowledge of a, b
+    // with a * b = c and a + b = d; public inputs (c, d) = (33, 14)
+    const ZK_VK: &[&str] = &[
+        "114be0b3e424b7d576245e7da6d397349276ab3318a2bd5fa8514c96423adb1bcf32a7b54229e2bf698575e61d995ec8",
+        "1371b852e626ee2f4321d5c93231f5a614aab43c03e0e125b0ecce8e1107ad516b79e788053ce0219df185e3a7c43caa",
+        "16c95c239ad610d7712dd4aa868388674dc79d64321c4452d14c74b87c997916c65b3baa9e83596555dcdf9db87e3842",
+        "16d8fb0b472d1ca077cbedbc9fa898827cf80462ad73a27d691145f5cad4a37f852039d53274ca4df23f5a0d1e728abe",
+        "073604c5460783320daf02b82e02469c705b2e6c79092cd292daafd95341a64e27368cb6fc7d26e1a20b1fada99ad85b",
+        "0b104ab480ad7baff9688881138fb484f8d111bd98267a0d42f86f9a1fa982d1a56e36ea744de9b5f2a7d9f935b1d18c",
+        "0937723cefa0e752e3762acb5191dac72507677ab53fbe61beae1e99e3500f530f33aef0163e4cd3747cd7efa973d23a",
+        "15756d79e0da199875184aad90e2c335ff727057bac5f006d75913b0d6f8853fd0d4f854b719881ff783cc2a6db4bf44",
+        "113f11248b81bde59bf27df53982ef4860435cc38722d3a3c9fe3e6481c0354ff7d2edc2d008d6f54a5a3f472d745db5",
+        "17f3be9616348e2ae21ca4c8fc8774a7251aad0fec34f57647db2b3fecd3de709ec4bc9ee0a138d3233e6d733129e486",
+        "031bf037b38d8d3ca59def5f47b853075600d17d2e8eab6c36cc7f667a9bab5c69671f30474c4e1f79ee0dcbb811158a",
+        "18c1007da13eb436a6d2c3b77f1387a4ea294fd1469d140a64cad405b72b6ecf99dd3643e940a8e17c693acb9825c64e",
+        "100528064161d7e5663d953364d2c5fafc5e73bce6962d34e912da61229acac28638a963b6a738a99690bf4e302046b5",
+        "0c40005d1e85e856b7342b6284c9d485d96a90c1e9cb33e8f61696fc5d6ec50b9d658cab0bc928398a6dd2bf87fc34ac",
+        "145503a648ea9c609df22625d8888899badf781d436e1647176900e52300dfa47b49285690016bec970e0c98a824824f",
+        "02bc78534216ae3dabbde5203f172c6424329af0add829484cad8e8746eff8aa52db716aa4e016b389ee93465fcf1390",
+        "0de4904285c392f386bcea8384a7a882351b09a5e46c843ee4f598a5947f278f74a304dff6753b9ce9eba05ef5d3bc43",
+        "0542600e66a096e8fb9bd4a9efaff1b987504ce09713421d60e2c27b34208ec5c0ba327f713cca3ef882cf8d7cbd58a1",
+        "0f956854f64f3d9097623033fc24390bb948470d9308ea305bbec087dc8bf021e924285dd7495ae90e9fa321a102ff79",
+        "009b9c537fa08d2ee4b5a5ec3c7c30062fffb3d0f0e4fe835c9f7a3f89eb7b75d138352db6942dba7b5b8e663f051667",
+    ];
+    const ZK_PROOF: &[&str] = &[
+        "00a2f219b20a153d6c5c9057fe2cc96ea78a4269128af7d7dc3f60dfa3da343d7242d23f9ce39cfb829fb4c0a8cee18a",
+        "0b25227d1abeef3877f7fd956672990a2e7d26e75425bc95227ab03ad769e9392c7a0f471f7c151e1584f2020ef39750",
+        "067302a30e48633a1d83b04639e44c06ab728b81f4672ff813eca9c5519a3fee72332c81dccae02f28563f5c4d067ff5",
+        "186e2d56717b469fa5872c4cf62bc5a3e4aa53a15b1e8d2014f5a7e8625d62ee468d88bbe876059376401be2adc17c77",
+        "01a73ab284702c2cdfcd77c87236c5e41845f39b08d07be12ae0460e05d2139213ee32e0cd6067a67ce96bea610203d9",
+        "06fa3fd40eb247e14026a00d9fe53a52249b89d5fee5a98e709dd065eaf33758ba569542ac994b59bc4365c6cd534f33",
+        "0ef6411d07f0358e730d66b5cea782c5622d425a615c63353aeafdb9ecf5815cdd852a022b19e4db8b09d4b6472223e8",
+        "1545f8c6db17351acec3369d24c837fd1b6806101ba0a229af1cb6a9fc8e29f05847dbed45c55c00e167fa5b62c30310",
+    ];
+
+    fn hex_bytes(env: &Env, parts: &[&str]) -> Bytes {
+        let mut out = Bytes::new(env);
+        for part in parts {
+            for i in (0..part.len()).step_by(2) {
+                out.push_back(u8::from_str_radix(&part[i..i + 2], 16).unwrap());
+            }
+        }
+        out
+    }
+
+    fn zk_inputs(env: &Env, values: &[u8]) -> Vec<BytesN<32>> {
+        let mut inputs = Vec::new(env);
+        for v in values {
+            let mut b = [0u8; 32];
+            b[31] = *v;
+            inputs.push_back(BytesN::from_array(env, &b));
+        }
+        inputs
+    }
+
+    #[test]
+    fn test_mint_with_proof_verified_on_chain() {
         let (env, client, _, _, creditor) = setup();
-        client.pause();
-        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            mint_one(&env, &client, &creditor);
-        }));
-        assert!(result.is_err());
+        let vk = hex_bytes(&env, ZK_VK);
+        let proof = hex_bytes(&env, ZK_PROOF);
+        assert_eq!(client.try_set_verifying_key(&vk.slice(0..700)), Err(Ok(Error::InvalidVerifyingKey)));
+        client.set_verifying_key(&vk);
+        assert_eq!(client.verifying_key(), Some(vk));
 
-        client.unpause();
-        let id = mint_one(&env, &client, &creditor);
-        assert_eq!(id, 1);
+        let currency = Address::generate(&env);
+        let debtor = BytesN::from_array(&env, &[1u8; 32]);
+        let mint_for = |debtor_hash: &BytesN<32>, face_value: i128, zk_proof: &ZkProof| client.try_mint_with_proof(
+            &creditor,
+            &None,
+            debtor_hash,
+            &face_value,
+            &currency,
+            &2_000_000_u64,
+            zk_proof,
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
+        );
+        let mint = |zk_proof: &ZkProof| mint_for(&debtor, 1_000_000, zk_proof);
+        let commitment = |debtor_hash: &BytesN<32>, face_value: i128| env.as_contract(&client.address, || {
+            let (hi, lo) = ReceivableTokenContract::proof_commitment(
+                &env, &creditor, debtor_hash, face_value, &currency, 2_000_000,
+            );
+            soroban_sdk::vec![&env, hi, lo]
+        });
+        let inputs = commitment(&debtor, 1_000_000);
+
+        // Tampered: C swapped for A, a valid point that breaks the pairing
+        let mut tampered = proof.slice(0..288);
+        tampered.append(&proof.slice(0..96));
+        let bad = ZkProof { proof: tampered, public_inputs: inputs.clone() };
+        assert_eq!(mint(&bad), Err(Ok(Error::InvalidProof)));
+
+        // Public inputs that aren't this receivable's commitment, or too few of them
+        let bad = ZkProof { proof: proof.clone(), public_inputs: zk_inputs(&env, &[33, 14]) };
+        assert_eq!(mint(&bad), Err(Ok(Error::ProofMismatch)));
+        let bad = ZkProof { proof: proof.clone(), public_inputs: inputs.slice(0..1) };
+        assert_eq!(mint(&bad), Err(Ok(Error::ProofMismatch)));
+        // The right commitment plus an input the key doesn't cover
+        let mut extra = inputs.clone();
+        extra.push_back(BytesN::from_array(&env, &[0u8; 32]));
+        let bad = ZkProof { proof: proof.clone(), public_inputs: extra };
+        assert_eq!(mint(&bad), Err(Ok(Error::InvalidProof)));
+
+        // Valid: minted without a verifier, storing the proof hash
+        let good = ZkProof { proof: proof.clone(), public_inputs: inputs.clone() };
+        assert_eq!(mint(&good), Ok(Ok(1)));
+        let hash: BytesN<32> = env.crypto().sha256(&proof).into();
+        assert_eq!(client.get_recv(&1).zk_proof_hash, hash);
+
+        // Replayed for a different receivable: the commitment no longer matches,
+        // and inputs recomputed for the new fields don't verify against the proof
+        let other_debtor = BytesN::from_array(&env, &[2u8; 32]);
+        assert_eq!(mint_for(&debtor, 9_000_000, &good), Err(Ok(Error::ProofMismatch)));
+        assert_eq!(mint_for(&other_debtor, 1_000_000, &good), Err(Ok(Error::ProofMismatch)));
+        let forged = ZkProof { proof: proof.clone(), public_inputs: commitment(&debtor, 9_000_000) };
+        assert_eq!(mint_for(&debtor, 9_000_000, &forged), Err(Ok(Error::InvalidProof)));
+        let forged = ZkProof { proof: proof.clone(), public_inputs: commitment(&other_debtor, 1_000_000) };
+        assert_eq!(mint_for(&other_debtor, 1_000_000, &forged), Err(Ok(Error::InvalidProof)));
+
+        // Each statement mints once, and the hash-only path is closed
+        assert_eq!(mint(&good), Err(Ok(Error::ProofAlreadyUsed)));
+        let verifier = client.get_verifiers().get(0).unwrap();
+        let res = client.try_mint(
+            &creditor, &verifier, &BytesN::from_array(&env, &[1u8; 32]), &1_000_000_i128,
+            &Address::generate(&env), &2_000_000_u64, &hash,
+            &500_u32, &String::from_str(&env, "ipfs://test"), &0_u64,
+        );
+        assert_eq!(res, Err(Ok(Error::ProofRequired)));
     }
 
     #[test]
-    #[should_panic(expected = "Error(Contract, #6)")]
-    fn test_zero_face_value_fails() {
+    fn test_mint_with_proof_falls_back_to_verifier() {
+        let (env, client, _, verifier, creditor) = setup();
+        let mut zk_proof = ZkProof { proof: hex_bytes(&env, ZK_PROOF), public_inputs: zk_inputs(&env, &[1, 2]) };
+        let mint = |verifier: &Option<Address>, zk_proof: &ZkProof| client.try_mint_with_proof(
+            &creditor,
+            verifier,
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &1_000_000_i128,
+            &Address::generate(&env),
+            &2_000_000_u64,
+            zk_proof,
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
+        );
+
+        // No key: the proof isn't checked, the verifier vouches for it
+        assert_eq!(mint(&None, &zk_proof), Err(Ok(Error::NotVerifier)));
+        assert_eq!(mint(&Some(Address::generate(&env)), &zk_proof), Err(Ok(Error::NotVerifier)));
+        assert_eq!(mint(&Some(verifier.clone()), &zk_proof), Ok(Ok(1)));
+
+        // Clearing a key restores this path
+        client.set_verifying_key(&hex_bytes(&env, ZK_VK));
+        assert_eq!(mint(&Some(verifier.clone()), &zk_proof), Err(Ok(Error::ProofMismatch)));
+        client.set_verifying_key(&Bytes::new(&env));
+        assert_eq!(client.verifying_key(), None);
+        assert_eq!(mint(&Some(verifier.clone()), &zk_proof), Err(Ok(Error::DuplicateProof)));
+        zk_proof.proof.set(0, zk_proof.proof.get(0).unwrap() ^ 1);
+        assert_eq!(mint(&Some(verifier), &zk_proof), Ok(Ok(2)));
+    }
+
+    #[test]
+    fn test_mark_defaulted_batch_after_grace() {
+        let (env, client, _, verifier, creditor) = setup();
+        let borrow_addr = Address::generate(&env);
+        client.set_borrow(&borrow_addr);
+        let active = mint_one(&env, &client, &creditor);
+        let paid = mint_one(&env, &client, &creditor);
+        let pledged = mint_one(&env, &client, &creditor);
+        client.lock(&pledged, &borrow_addr);
+        let ids = soroban_sdk::vec![&env, active, paid, pledged, 99];
+        assert_eq!(client.try_mark_defaulted_batch(&ids), Err(Ok(Error::AutoDefaultOff)));
+
+        let grace = 7 * 86_400;
+        client.set_default_grace_secs(&grace);
+        assert_eq!(client.default_grace_secs(), Some(grace));
+        let deadline = client.get_recv(&active).maturity_date + grace;
+
+        // Paid in full on the last second of the grace period
+        env.ledger().set_timestamp(deadline);
+        client.record_payment(&verifier, &paid, &1_000_000);
+        assert_eq!(client.mark_defaulted_batch(&ids), 0);
+
+        env.ledger().set_timestamp(deadline + 1);
+        assert_eq!(client.mark_defaulted_batch(&ids), 2);
+        assert_eq!(client.get_recv(&active).status, ReceivableStatus::Defaulted);
+        assert_eq!(client.get_recv(&pledged).status, ReceivableStatus::Defaulted);
+        assert_eq!(client.get_recv(&paid).status, ReceivableStatus::Settled);
+        assert_eq!(client.total_active(), 0);
+        let agg = client.get_aggregates();
+        assert_eq!((agg.total_face_active, agg.total_face_settled, agg.total_face_defaulted), (0, 1_000_000, 2_000_000));
+
+        // The pledge's lock is gone; the pool can still unwind its loan
+        assert_eq!(client.locked_by(&pledged), None);
+        assert_eq!(client.lock_count(&borrow_addr), 0);
+        client.unlock(&pledged, &borrow_addr);
+        assert_eq!(client.get_recv(&pledged).status, ReceivableStatus::Defaulted);
+        assert_eq!(client.mark_defaulted_batch(&ids), 0);
+    }
+
+    #[test]
+    fn test_duplicate_proof_hash_rejected_for_good() {
+        let (env, client, _, verifier, creditor) = setup();
+        let hash = BytesN::from_array(&env, &[9u8; 32]);
+        let mint = || client.try_mint(
+            &creditor,
+            &verifier,
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &1_000_000_i128,
+            &Address::generate(&env),
+            &2_000_000_u64,
+            &hash,
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
+        );
+        assert_eq!(client.proof_receivable(&hash), None);
+        assert_eq!(mint(), Ok(Ok(1)));
+        assert_eq!(client.proof_receivable(&hash), Some(1));
+
+        assert_eq!(mint(), Err(Ok(Error::DuplicateProof)));
+        assert_eq!(client.total_minted(), 1);
+
+        // Settled is terminal, but the invoice behind the proof was already financed once
+        client.settle(&1);
+        assert_eq!(mint(), Err(Ok(Error::DuplicateProof)));
+        assert_eq!(client.proof_receivable(&hash), Some(1));
+    }
+
+    #[test]
+    fn test_mint_fee_paid_to_recipient_before_minting() {
         let (env, client, _, _, creditor) = setup();
+        let sac = env.register_stellar_asset_contract_v2(Address::generate(&env));
+        let fee_token = token::Client::new(&env, &sac.address());
+        token::StellarAssetClient::new(&env, &sac.address()).mint(&creditor, &2_000);
+        let treasury = Address::generate(&env);
+
+        // A zero fee charges nothing and mints exactly as without one
+        client.set_mint_fee(&Some(MintFee { token: sac.address(), flat: 0, bps: 0, recipient: Some(treasury.clone()) }));
+        let id = mint_one(&env, &client, &creditor);
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(<(u64, i128, i128)>::from_val(&env, &data), (id, 1_000_000, 0));
+        assert_eq!(fee_token.balance(&creditor), 2_000);
+
+        client.set_mint_fee(&Some(MintFee { token: sac.address(), flat: 1_500, bps: 0, recipient: Some(treasury.clone()) }));
+        let id = mint_one(&env, &client, &creditor);
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(<(u64, i128, i128)>::from_val(&env, &data), (id, 1_000_000, 1_500));
+        assert_eq!(fee_token.balance(&treasury), 1_500);
+        assert_eq!(client.mint_fees_accrued(&sac.address()), 0);
+
+        // 500 left: a clean error and nothing minted
         let currency = Address::generate(&env);
-        client.mint(
-            &creditor, &BytesN::from_array(&env, &[1u8; 32]), &0_i128,
-            &currency, &2_000_000_u64, &BytesN::from_array(&env, &[2u8; 32]),
-            &500_u32, &String::from_str(&env, "ipfs://test"),
+        let res = client.try_mint(
+            &creditor,
+            &client.get_verifiers().get(0).unwrap(),
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &1_000_000_i128,
+            &currency,
+            &2_000_000_u64,
+            &proof_hash(&env, &client),
+            &500_u32,
+            &String::from_str(&env, "ipfs://test"),
+            &0_u64,
         );
+        assert_eq!(res, Err(Ok(Error::InsufficientFeeBalance)));
+        assert_eq!(client.total_minted(), 2);
+        assert_eq!(fee_token.balance(&creditor), 500);
     }
-}
\ No newline at end of file
+}
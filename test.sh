@@ -31,17 +31,7 @@ run_tests() {
 # Run tests for each contract
 run_tests "receivable_token"
 run_tests "lending_vault"
-
-# Borrow contract tests require integration (cross-contract)
-# Run unit tests only
-echo -e "\n${YELLOW}━━━ Testing: borrow_contract (compile check) ━━━${NC}"
-if cargo check -p borrow_contract 2>&1; then
-    echo -e "${GREEN}✓ borrow_contract compiles${NC}"
-    PASSED=$((PASSED + 1))
-else
-    echo -e "${RED}✗ borrow_contract compile failed${NC}"
-    FAILED=$((FAILED + 1))
-fi
+run_tests "borrow_contract"
 
 echo ""
 echo "============================================"
@@ -51,13 +51,15 @@ RECV_ID=$(stellar contract invoke \
     -- \
     mint \
     --creditor $CREDITOR \
+    --verifier $VERIFIER \
     --debtor_hash $MOCK_HASH \
     --face_value 10000000000 \
     --currency CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2OOTWHUF \
     --maturity_date 1800000000 \
     --zk_proof_hash $MOCK_HASH \
     --risk_score 500 \
-    --metadata_uri "ipfs://QmTestReceivableMetadata123")
+    --metadata_uri "ipfs://QmTestReceivableMetadata123" \
+    --active_from 0)
 
 print_ok "Minted receivable ID: $RECV_ID"
 